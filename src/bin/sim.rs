@@ -0,0 +1,347 @@
+// runs lots of games without any rendering and prints their statistics
+//
+//	--games N				seeds 0..N, 100 by default
+//	--seeds A..B			explicit range of seeds
+//	--bot heuristic|random	how pieces are placed
+//	--weights W				"height lines holes bumpiness wells" for heuristic
+//	--randomizer classic|bag
+//	--pieces tetromino|pentomino|tromino
+//	--rules FILE			ruleset with the board, randomizer and pieces
+//	--width W, --height H	board size
+//	--max-pieces N			games are cut off after it, 10000 by default
+//	--threads N				all cores by default
+//	--format json|csv
+
+use {
+	tetris::tetris::{
+		piece_set,
+		tetrominoes,
+		Bot,
+		Piece,
+		Randomizer,
+		Ruleset,
+		Setup,
+		Tetris,
+		Weights,
+	},
+	rand::{
+		rngs::StdRng,
+		Rng,
+		SeedableRng,
+	},
+	std::{
+		ops::Range,
+		process,
+		thread,
+		time::Instant,
+	},
+};
+
+
+#[derive(Clone, Copy)]
+enum Player {
+	Heuristic(Weights),
+	Random,
+}
+
+#[derive(Clone, Copy, PartialEq)]
+enum Format {
+	Json,
+	Csv,
+}
+
+struct Options {
+	seeds: Range<u64>,
+	player: Player,
+	randomizer: Randomizer,
+	pieces: (String, Vec<Piece>),
+	width: usize,
+	height: usize,
+	max_pieces: usize,
+	threads: usize,
+	format: Format,
+}
+
+// result of a single game
+struct Outcome {
+	lines: usize,
+	score: usize,
+	pieces: usize,
+	topped_out: bool,
+}
+
+
+fn main() {
+	let options = parse(std::env::args().skip(1).collect());
+
+	let start = Instant::now();
+	let outcomes = run(&options);
+	let seconds = start.elapsed().as_secs_f64();
+
+	print(&options, &outcomes, seconds);
+}
+
+
+fn parse(args: Vec<String>) -> Options {
+	let mut options = Options {
+		seeds: 0..100,
+		player: Player::Heuristic(Weights::default()),
+		randomizer: Randomizer::Classic,
+		pieces: (String::from("tetromino"), tetrominoes()),
+		width: 10,
+		height: 16,
+		max_pieces: 10_000,
+		threads: thread::available_parallelism().map_or(1, usize::from),
+		format: Format::Json,
+	};
+	let mut weights = Weights::default();
+	let mut random = false;
+
+	let mut args = args.into_iter();
+	while let Some(arg) = args.next() {
+		let value = args.next().unwrap_or_else(|| fail(&format!("No value for {arg}")));
+
+		match arg.as_str() {
+			"--games" => options.seeds = 0..number(&arg, &value),
+			"--seeds" => {
+				let (a, b) = value
+					.split_once("..")
+					.unwrap_or_else(|| fail("Seeds should look like A..B"));
+				options.seeds = number(&arg, a)..number(&arg, b);
+			}
+			"--bot" => random = match value.as_str() {
+				"heuristic" => false,
+				"random" => true,
+				_ => fail(&format!("Unknown bot {value}")),
+			},
+			"--weights" => weights = value
+				.parse()
+				.unwrap_or_else(|err| fail(&format!("Invalid weights: {err}"))),
+			"--randomizer" => options.randomizer = match value.as_str() {
+				"classic" => Randomizer::Classic,
+				"bag" => Randomizer::Bag,
+				_ => fail(&format!("Unknown randomizer {value}")),
+			},
+			"--pieces" => {
+				let pieces = piece_set(&value)
+					.unwrap_or_else(|| fail(&format!("Unknown pieces {value}")));
+				options.pieces = (value, pieces);
+			}
+			// later options override the ruleset
+			"--rules" => {
+				let ruleset = Ruleset::load(value.as_ref())
+					.unwrap_or_else(|err| fail(&format!("{value}: {err}")));
+				options.width = ruleset.width;
+				options.height = ruleset.height;
+				options.randomizer = ruleset.randomizer;
+				options.pieces = (value, ruleset.pieces);
+			}
+			"--width" => options.width = number(&arg, &value),
+			"--height" => options.height = number(&arg, &value),
+			"--max-pieces" => options.max_pieces = number(&arg, &value),
+			"--threads" => options.threads = number::<usize>(&arg, &value).max(1),
+			"--format" => options.format = match value.as_str() {
+				"json" => Format::Json,
+				"csv" => Format::Csv,
+				_ => fail(&format!("Unknown format {value}")),
+			},
+			_ => fail(&format!("Unknown option {arg}")),
+		}
+	}
+
+	// tetriminos need some room to spawn and turn around
+	if options.width < 4 || options.height < 4 || options.width > 100 || options.height > 100 {
+		fail("Board should be from 4 to 100 blocks in each direction");
+	}
+
+	options.player = if random { Player::Random } else { Player::Heuristic(weights) };
+	options
+}
+
+fn number<T: std::str::FromStr>(arg: &str, value: &str) -> T {
+	value.parse().unwrap_or_else(|_| fail(&format!("Invalid number for {arg}: {value}")))
+}
+
+fn fail(msg: &str) -> ! {
+	eprintln!("{msg}");
+	process::exit(1);
+}
+
+
+// seeds are dealt to threads one by one, outcomes are in the order of seeds
+fn run(options: &Options) -> Vec<Outcome> {
+	let seeds: Vec<u64> = options.seeds.clone().collect();
+	let threads = options.threads.min(seeds.len()).max(1);
+
+	let mut outcomes: Vec<(u64, Outcome)> = thread::scope(|scope| {
+		let handles: Vec<_> = (0..threads)
+			.map(|i| {
+				let seeds = seeds.iter().copied().skip(i).step_by(threads);
+				scope.spawn(move || {
+					seeds.map(|seed| (seed, play(options, seed))).collect::<Vec<_>>()
+				})
+			})
+			.collect();
+
+		handles.into_iter().flat_map(|handle| handle.join().unwrap()).collect()
+	});
+
+	outcomes.sort_by_key(|(seed, _)| *seed);
+	outcomes.into_iter().map(|(_, outcome)| outcome).collect()
+}
+
+// places pieces one by one until the game is over or the cap is reached
+fn play(options: &Options, seed: u64) -> Outcome {
+	let mut tetris = Tetris::with(Setup {
+		seed,
+		width: options.width,
+		height: options.height,
+		randomizer: options.randomizer.clone(),
+		pieces: options.pieces.1.clone(),
+	});
+	let mut rng = StdRng::seed_from_u64(seed);
+	let bot = match options.player {
+		Player::Heuristic(weights) => Some(Bot::new(weights)),
+		Player::Random => None,
+	};
+
+	while !tetris.is_over() && tetris.pieces() < options.max_pieces {
+		// spawns the next tetrimino
+		tetris.proceed();
+		if tetris.is_over() {
+			break;
+		}
+
+		let moves = match &bot {
+			Some(bot) => bot.best(&tetris),
+			None => {
+				let mut placements = tetris.placements();
+				match placements.len() {
+					0 => vec!(),
+					len => placements.swap_remove(rng.gen_range(0..len)).moves,
+				}
+			}
+		};
+
+		for msg in moves {
+			tetris.react_to(msg);
+		}
+
+		let pieces = tetris.pieces();
+		while tetris.pieces() == pieces && !tetris.is_over() {
+			tetris.proceed();
+		}
+	}
+
+	Outcome {
+		lines: tetris.lines(),
+		score: tetris.score(),
+		pieces: tetris.pieces(),
+		topped_out: tetris.is_over(),
+	}
+}
+
+
+fn print(options: &Options, outcomes: &[Outcome], seconds: f64) {
+	let games = outcomes.len();
+	let mean = |f: fn(&Outcome) -> usize| {
+		outcomes.iter().map(f).sum::<usize>() as f64 / games.max(1) as f64
+	};
+
+	let mut scores: Vec<usize> = outcomes.iter().map(|outcome| outcome.score).collect();
+	scores.sort();
+	// nearest-rank percentile
+	let percentile = |p: usize| match games {
+		0 => 0,
+		_ => scores[(p * games).div_ceil(100).max(1) - 1],
+	};
+
+	let topped_out = outcomes.iter().filter(|outcome| outcome.topped_out).count();
+
+	let bot = match options.player {
+		Player::Heuristic(_) => "heuristic",
+		Player::Random => "random",
+	};
+	let randomizer = match options.randomizer {
+		Randomizer::Classic => "classic",
+		Randomizer::Bag => "bag",
+		Randomizer::Fixed(_) => "fixed",
+	};
+
+	let text = |text: &str| Value::Text(String::from(text));
+	let number = |number: String| Value::Number(number);
+	let fields: Vec<(&str, Value)> = vec!(
+		("bot", text(bot)),
+		("randomizer", text(randomizer)),
+		("pieces", text(&options.pieces.0)),
+		("width", number(options.width.to_string())),
+		("height", number(options.height.to_string())),
+		("games", number(games.to_string())),
+		("mean_lines", number(format!("{:.2}", mean(|outcome| outcome.lines)))),
+		("mean_score", number(format!("{:.2}", mean(|outcome| outcome.score)))),
+		("mean_pieces", number(format!("{:.2}", mean(|outcome| outcome.pieces)))),
+		("score_min", number(percentile(0).to_string())),
+		("score_p25", number(percentile(25).to_string())),
+		("score_median", number(percentile(50).to_string())),
+		("score_p75", number(percentile(75).to_string())),
+		("score_max", number(percentile(100).to_string())),
+		("top_out_rate", number(format!("{:.4}", topped_out as f64 / games.max(1) as f64))),
+		("games_per_second", number(format!("{:.2}", games as f64 / seconds.max(1e-9)))),
+	);
+
+	match options.format {
+		Format::Json => {
+			let body: Vec<String> = fields
+				.iter()
+				.map(|(key, value)| format!("  \"{key}\": {}", value.json()))
+				.collect();
+			println!("{{\n{}\n}}", body.join(",\n"));
+		}
+		Format::Csv => {
+			let keys: Vec<&str> = fields.iter().map(|(key, _)| *key).collect();
+			let values: Vec<String> = fields.iter().map(|(_, value)| value.csv()).collect();
+			println!("{}", keys.join(","));
+			println!("{}", values.join(","));
+		}
+	}
+}
+
+
+// values of the report, text is quoted the way each format wants it
+enum Value {
+	Text(String),
+	Number(String),
+}
+
+impl Value {
+	fn json(&self) -> String {
+		match self {
+			Value::Number(number) => number.clone(),
+			Value::Text(text) => {
+				let mut quoted = String::from('"');
+				for c in text.chars() {
+					match c {
+						'"' => quoted.push_str("\\\""),
+						'\\' => quoted.push_str("\\\\"),
+						'\n' => quoted.push_str("\\n"),
+						'\r' => quoted.push_str("\\r"),
+						'\t' => quoted.push_str("\\t"),
+						c if c.is_control() => quoted.push_str(&format!("\\u{:04x}", c as u32)),
+						c => quoted.push(c),
+					}
+				}
+				quoted.push('"');
+				quoted
+			}
+		}
+	}
+
+	fn csv(&self) -> String {
+		match self {
+			Value::Number(number) => number.clone(),
+			Value::Text(text) if text.contains([',', '"', '\n', '\r']) =>
+				format!("\"{}\"", text.replace('"', "\"\"")),
+			Value::Text(text) => text.clone(),
+		}
+	}
+}
@@ -0,0 +1,151 @@
+// the same game in the terminal, it's playable over ssh
+//
+//	arrows				shifting and descent
+//	ctrl + arrows		rotation
+//	up					drop
+//	ctrl + down			hold
+//	esc, q				exit
+
+use {
+	tetris::{
+		game::Msg,
+		tetris::Tetris,
+	},
+	crossterm::{
+		cursor,
+		event::{
+			self,
+			Event,
+			KeyCode,
+			KeyEvent,
+			KeyEventKind,
+			KeyModifiers,
+		},
+		style::{
+			Print,
+			ResetColor,
+			SetForegroundColor,
+			Color as TermColor,
+		},
+		terminal,
+		queue,
+	},
+	std::{
+		io::{
+			self,
+			Stdout,
+			Write,
+		},
+		time::Instant,
+	},
+};
+
+
+// each block is drawn as two characters to look square
+const BLOCK: &str = "██";
+
+
+fn main() -> io::Result<()> {
+	let mut stdout = io::stdout();
+
+	terminal::enable_raw_mode()?;
+	queue!(stdout, terminal::EnterAlternateScreen, cursor::Hide)?;
+
+	let result = play(&mut stdout);
+
+	queue!(stdout, ResetColor, cursor::Show, terminal::LeaveAlternateScreen)?;
+	stdout.flush()?;
+	terminal::disable_raw_mode()?;
+
+	result
+}
+
+
+fn play(stdout: &mut Stdout) -> io::Result<()> {
+	let mut tetris = Tetris::new();
+	let mut last = Instant::now();
+
+	loop {
+		draw(stdout, &tetris)?;
+
+		// waits for a key no longer than the descent is due
+		let timeout = tetris.level_time().saturating_sub(last.elapsed());
+		if event::poll(timeout)? {
+			if let Event::Key(key) = event::read()? {
+				if key.kind == KeyEventKind::Release {
+					continue;
+				}
+				match action(key) {
+					Some(Msg::Exit) => return Ok(()),
+					Some(msg) => tetris.react_to(msg),
+					None => (),
+				}
+			}
+		}
+
+		let now = Instant::now();
+		tetris.tick(now - last);
+		last = now;
+	}
+}
+
+
+fn action(key: KeyEvent) -> Option<Msg> {
+	let ctrl = key.modifiers.contains(KeyModifiers::CONTROL);
+
+	match (key.code, ctrl) {
+		(KeyCode::Right, false) => Some(Msg::ShiftRgt),
+		(KeyCode::Left, false) => Some(Msg::ShiftLft),
+		(KeyCode::Right, true) => Some(Msg::Clockwise),
+		(KeyCode::Left, true) => Some(Msg::Counterclockwise),
+		(KeyCode::Down, false) => Some(Msg::Proceed),
+		(KeyCode::Down, true) => Some(Msg::Hold),
+		(KeyCode::Up, _) => Some(Msg::Drop),
+		(KeyCode::Esc, _) | (KeyCode::Char('q'), _) => Some(Msg::Exit),
+		(KeyCode::Char('c'), true) => Some(Msg::Exit),
+		_ => None,
+	}
+}
+
+
+fn draw(stdout: &mut Stdout, tetris: &Tetris) -> io::Result<()> {
+	queue!(stdout, terminal::Clear(terminal::ClearType::All))?;
+
+	// the top row is printed first
+	for (coord, tile) in tetris.map_iter() {
+		queue!(stdout,
+			   cursor::MoveTo(2 * coord.x as u16, (tetris.height() - 1 - coord.y) as u16),
+			   SetForegroundColor(TermColor::AnsiValue(tile.color.to_ansi())),
+			   Print(BLOCK))?;
+	}
+
+	// next tetriminos and the held one are shown to the right
+	let next = tetris.preview().into_iter().map(Some);
+	let shapes = next.chain([None, tetris.held()]).enumerate();
+
+	for (i, id) in shapes {
+		let (blocks, tile) = match id {
+			Some(id) => tetris.shape_of(id),
+			None => continue,
+		};
+
+		// tetriminos are laid horizontally one under another
+		for block in blocks {
+			queue!(stdout,
+				   cursor::MoveTo(24 + 2 * block.y as u16, 3 * i as u16 + block.x as u16),
+				   SetForegroundColor(TermColor::AnsiValue(tile.color.to_ansi())),
+				   Print(BLOCK))?;
+		}
+	}
+
+	queue!(stdout,
+		   ResetColor,
+		   cursor::MoveTo(0, tetris.height() as u16 + 1),
+		   Print(format!("score {}  lines {}", tetris.score(), tetris.lines())))?;
+
+	if tetris.is_over() {
+		queue!(stdout, cursor::MoveTo(0, tetris.height() as u16 + 2), Print("Game over, q to exit"))?;
+	}
+
+	stdout.flush()
+}
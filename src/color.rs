@@ -1,9 +1,21 @@
-use iced::Color as IcedColor;
+use std::str::FromStr;
 
 pub const N_COLORS: usize = 8;
 
+// tetriminos without a color of their own take these by their serial numbers,
+// vinous is too dark to be seen on dark backgrounds
+pub const PIECE_COLORS: [Color; N_COLORS - 1] = [
+	Color::Red,
+	Color::Grn,
+	Color::Blu,
+	Color::Wht,
+	Color::Ylw,
+	Color::Pnk,
+	Color::Gry,
+];
 
-#[derive(Debug, Clone, Copy, PartialEq)]
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash)]
 pub enum Color {
 	Red,
 	Grn,
@@ -13,6 +25,7 @@ pub enum Color {
 	Pnk,
 	Vin,
 	Gry,
+	Grb,	// garbage, never picked for tetriminos
 	Non,
 }
 
@@ -29,17 +42,49 @@ impl Color {
 		 Color::Gry]
 	}
 	
-	pub fn to_rgb(&self) -> IcedColor {
+	// the name it's known by in rulesets and themes
+	pub fn name(&self) -> &'static str {
+		match self {
+			Color::Red => "red",
+			Color::Grn => "green",
+			Color::Blu => "blue",
+			Color::Wht => "white",
+			Color::Ylw => "yellow",
+			Color::Pnk => "pink",
+			Color::Vin => "vinous",
+			Color::Gry => "gray",
+			Color::Grb => "garbage",
+			Color::Non => "none",
+		}
+	}
+
+	// the closest color of the 256-color terminal palette
+	pub fn to_ansi(&self) -> u8 {
 		match self {
-			Color::Red => IcedColor::from_rgb8(159,  70,  54),
-			Color::Grn => IcedColor::from_rgb8(  7,  87,  91),
-			Color::Blu => IcedColor::from_rgb8(120, 165, 163),
-			Color::Wht => IcedColor::from_rgb8(241, 220, 201),
-			Color::Ylw => IcedColor::from_rgb8(225, 177, 106),
-			Color::Pnk => IcedColor::from_rgb8(255, 204, 187),
-			Color::Vin => IcedColor::from_rgb8( 30,   0,   0),
-			Color::Gry => IcedColor::from_rgb8(254, 155, 151),
-			Color::Non => IcedColor::from_rgb8(  0,   0,   0),
+			Color::Red => 131,
+			Color::Grn =>  30,
+			Color::Blu => 109,
+			Color::Wht => 230,
+			Color::Ylw => 180,
+			Color::Pnk => 224,
+			Color::Vin =>  52,
+			Color::Gry => 210,
+			Color::Grb => 244,
+			Color::Non =>  16,
 		}
 	}
 }
+
+
+// names of colors that may be picked for tetriminos
+impl FromStr for Color {
+	type Err = String;
+
+	fn from_str(name: &str) -> Result<Color, String> {
+		let lower = name.to_lowercase();
+		Color::all()
+			.into_iter()
+			.find(|color| color.name() == lower)
+			.ok_or_else(|| format!("unknown color {name}"))
+	}
+}
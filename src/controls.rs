@@ -0,0 +1,394 @@
+// keys the players control tetriminos with, kept in the config dir as
+//
+//	[main]
+//	left = ["Left"]
+//	clockwise = ["Ctrl+Right", "X"]
+//	...
+
+use {
+	iced::keyboard::{
+		KeyCode,
+		Modifiers,
+	},
+	crate::game::Msg,
+	serde::{
+		Deserialize,
+		Serialize,
+	},
+	std::{
+		collections::BTreeMap,
+		fmt,
+		fs,
+		io,
+		path::PathBuf,
+		str::FromStr,
+	},
+};
+
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq, PartialOrd, Ord, Hash, Serialize, Deserialize)]
+#[serde(rename_all = "snake_case")]
+pub enum Action {
+	Left,
+	Right,
+	Clockwise,
+	Counterclockwise,
+	SoftDrop,
+	HardDrop,
+	Hold,
+	Share,
+}
+
+impl Action {
+	pub const ALL: [Action; 8] = [
+		Action::Left,
+		Action::Right,
+		Action::Clockwise,
+		Action::Counterclockwise,
+		Action::SoftDrop,
+		Action::HardDrop,
+		Action::Hold,
+		Action::Share,
+	];
+
+	pub fn msg(&self) -> Msg {
+		match self {
+			Action::Left => Msg::ShiftLft,
+			Action::Right => Msg::ShiftRgt,
+			Action::Clockwise => Msg::Clockwise,
+			Action::Counterclockwise => Msg::Counterclockwise,
+			Action::SoftDrop => Msg::Proceed,
+			Action::HardDrop => Msg::Drop,
+			Action::Hold => Msg::Hold,
+			Action::Share => Msg::Share,
+		}
+	}
+}
+
+impl fmt::Display for Action {
+	fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+		let name = match self {
+			Action::Left => "Shift left",
+			Action::Right => "Shift right",
+			Action::Clockwise => "Rotate clockwise",
+			Action::Counterclockwise => "Rotate counterclockwise",
+			Action::SoftDrop => "Soft drop",
+			Action::HardDrop => "Hard drop",
+			Action::Hold => "Hold",
+			Action::Share => "Copy the board as fumen",
+		};
+		f.pad(name)
+	}
+}
+
+
+// names of keys that may be bound, they are the same as in KeyCode
+macro_rules! keys {
+	($($key:ident),* $(,)?) => {
+		const KEYS: &[(KeyCode, &str)] = &[$((KeyCode::$key, stringify!($key))),*];
+	};
+}
+
+keys!(
+	A, B, C, D, E, F, G, H, I, J, K, L, M, N, O, P, Q, R, S, T, U, V, W, X, Y, Z,
+	Key0, Key1, Key2, Key3, Key4, Key5, Key6, Key7, Key8, Key9,
+	F1, F2, F3, F4, F5, F6, F7, F8, F9, F10, F11, F12,
+	Left, Up, Right, Down, Space, Enter, Tab, Backspace,
+	Insert, Delete, Home, End, PageUp, PageDown,
+	LShift, RShift, LAlt, RAlt,
+	Comma, Period, Slash, Semicolon, Apostrophe, Backslash, Grave,
+	LBracket, RBracket, Minus, Equals,
+	Numpad0, Numpad1, Numpad2, Numpad3, Numpad4,
+	Numpad5, Numpad6, Numpad7, Numpad8, Numpad9,
+	NumpadAdd, NumpadSubtract, NumpadMultiply, NumpadDivide, NumpadEnter,
+);
+
+
+// a key alone or together with ctrl, it's written like Ctrl+Left
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(try_from = "String", into = "String")]
+pub struct Key {
+	pub code: KeyCode,
+	pub ctrl: bool,
+}
+
+impl Key {
+	pub fn new(code: KeyCode, modifiers: Modifiers) -> Key {
+		Key { code, ctrl: modifiers.control() }
+	}
+
+	// whether it's known by its name
+	pub fn is_named(&self) -> bool {
+		KEYS.iter().any(|(code, _)| *code == self.code)
+	}
+}
+
+impl fmt::Display for Key {
+	fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+		if self.ctrl {
+			write!(f, "Ctrl+")?;
+		}
+		match KEYS.iter().find(|(code, _)| *code == self.code) {
+			Some((_, name)) => write!(f, "{name}"),
+			None => write!(f, "{:?}", self.code),
+		}
+	}
+}
+
+impl FromStr for Key {
+	type Err = String;
+
+	fn from_str(string: &str) -> Result<Key, String> {
+		let (ctrl, name) = match string.strip_prefix("Ctrl+") {
+			Some(name) => (true, name),
+			None => (false, string),
+		};
+
+		KEYS.iter()
+			.find(|(_, known)| *known == name)
+			.map(|&(code, _)| Key { code, ctrl })
+			.ok_or_else(|| format!("unknown key {string}"))
+	}
+}
+
+impl TryFrom<String> for Key {
+	type Error = String;
+
+	fn try_from(string: String) -> Result<Key, String> {
+		string.parse()
+	}
+}
+
+impl From<Key> for String {
+	fn from(key: Key) -> String {
+		key.to_string()
+	}
+}
+
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum Preset {
+	Arrows,		// arrows, ctrl + arrows for rotation, up for drop, ctrl + down for hold, F to share
+	Wasd,		// A, D for shifting, Q, E for rotation, S for descent, W for drop, R for hold
+	Guideline,	// arrows for shifting, Z, X or up for rotation, space for drop, C or shift for hold, F to share
+}
+
+impl Preset {
+	pub const ALL: [Preset; 3] = [Preset::Arrows, Preset::Wasd, Preset::Guideline];
+}
+
+impl fmt::Display for Preset {
+	fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+		let name = match self {
+			Preset::Arrows => "Arrows",
+			Preset::Wasd => "WASD",
+			Preset::Guideline => "Guideline",
+		};
+		write!(f, "{name}")
+	}
+}
+
+
+// keys of each action, a key belongs to a single action
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+#[serde(transparent)]
+pub struct Bindings {
+	keys: BTreeMap<Action, Vec<Key>>,
+}
+
+impl Bindings {
+	pub fn preset(preset: Preset) -> Bindings {
+		let key = |name: &str| name.parse::<Key>().unwrap();
+		let keys: Vec<(Action, &[&str])> = match preset {
+			Preset::Arrows => vec!(
+				(Action::Left, &["Left"]),
+				(Action::Right, &["Right"]),
+				(Action::Clockwise, &["Ctrl+Right"]),
+				(Action::Counterclockwise, &["Ctrl+Left"]),
+				(Action::SoftDrop, &["Down"]),
+				(Action::HardDrop, &["Up"]),
+				(Action::Hold, &["Ctrl+Down"]),
+				(Action::Share, &["F"]),
+			),
+			Preset::Wasd => vec!(
+				(Action::Left, &["A"]),
+				(Action::Right, &["D"]),
+				(Action::Clockwise, &["E"]),
+				(Action::Counterclockwise, &["Q"]),
+				(Action::SoftDrop, &["S"]),
+				(Action::HardDrop, &["W"]),
+				(Action::Hold, &["R"]),
+			),
+			Preset::Guideline => vec!(
+				(Action::Left, &["Left"]),
+				(Action::Right, &["Right"]),
+				(Action::Clockwise, &["X", "Up"]),
+				(Action::Counterclockwise, &["Z"]),
+				(Action::SoftDrop, &["Down"]),
+				(Action::HardDrop, &["Space"]),
+				(Action::Hold, &["C", "LShift"]),
+				(Action::Share, &["F"]),
+			),
+		};
+
+		Bindings {
+			keys: keys
+				.into_iter()
+				.map(|(action, names)| (action, names.iter().map(|name| key(name)).collect()))
+				.collect(),
+		}
+	}
+
+	// the key with ctrl falls back to the key alone if it isn't bound
+	pub fn action(&self, code: KeyCode, modifiers: Modifiers) -> Option<Msg> {
+		let key = Key::new(code, modifiers);
+		self.find(key)
+			.or_else(|| if key.ctrl { self.find(Key { ctrl: false, ..key }) } else { None })
+			.map(|action| action.msg())
+	}
+
+	pub fn is_bound(&self, key: Key) -> bool {
+		self.find(key).is_some()
+	}
+
+	fn find(&self, key: Key) -> Option<Action> {
+		self.keys
+			.iter()
+			.find(|(_, keys)| keys.contains(&key))
+			.map(|(&action, _)| action)
+	}
+
+	pub fn keys(&self, action: Action) -> &[Key] {
+		self.keys.get(&action).map_or(&[], Vec::as_slice)
+	}
+
+	// the key is taken from the action it was bound to before
+	pub fn bind(&mut self, action: Action, key: Key) {
+		self.unbind(key);
+		self.keys.entry(action).or_default().push(key);
+	}
+
+	// returns false if the key wasn't bound
+	pub fn unbind(&mut self, key: Key) -> bool {
+		let bound = self.is_bound(key);
+		for keys in self.keys.values_mut() {
+			keys.retain(|other| *other != key);
+		}
+		bound
+	}
+
+	fn all(&self) -> impl Iterator<Item = Key> + '_ {
+		self.keys.values().flatten().copied()
+	}
+
+	pub fn clear(&mut self, action: Action) {
+		self.keys.remove(&action);
+	}
+}
+
+
+// the main player plays alone and on the right in versus,
+// the second one plays on the left
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+#[serde(default)]
+pub struct Controls {
+	pub main: Bindings,
+	pub second: Bindings,
+}
+
+impl Default for Controls {
+	fn default() -> Controls {
+		Controls {
+			main: Bindings::preset(Preset::Arrows),
+			second: Bindings::preset(Preset::Wasd),
+		}
+	}
+}
+
+impl Controls {
+	pub fn path() -> Option<PathBuf> {
+		dirs::config_dir().map(|dir| dir.join("tetris").join("controls.toml"))
+	}
+
+	// the default keys are used if the file is missing or broken, a broken one is reported
+	pub fn load() -> Controls {
+		Controls::read().unwrap_or_else(|err| {
+			eprintln!("{err}");
+			Controls::default()
+		})
+	}
+
+	// the defaults if there is no file yet,
+	// keys of the main player are taken from the second one
+	pub fn read() -> Result<Controls, String> {
+		let path = match Controls::path() {
+			Some(path) => path,
+			None => return Ok(Controls::default()),
+		};
+		let content = match fs::read_to_string(&path) {
+			Ok(content) => content,
+			Err(err) if err.kind() == io::ErrorKind::NotFound => return Ok(Controls::default()),
+			Err(err) => return Err(format!("{}: {err}", path.display())),
+		};
+		let mut controls: Controls = toml::from_str(&content)
+			.map_err(|err| format!("{}: {}", path.display(), err.message()))?;
+
+		let main: Vec<Key> = controls.main.all().collect();
+		for key in main {
+			controls.second.unbind(key);
+		}
+		Ok(controls)
+	}
+
+	// a key belongs to a single player, returns true if it's taken from the other one
+	pub fn bind(&mut self, second: bool, action: Action, key: Key) -> bool {
+		let (bindings, other) = self.players(second);
+		bindings.bind(action, key);
+		other.unbind(key)
+	}
+
+	// keys of the preset are taken from the other player
+	pub fn preset(&mut self, second: bool, preset: Preset) {
+		let (bindings, other) = self.players(second);
+		*bindings = Bindings::preset(preset);
+		for key in bindings.all() {
+			other.unbind(key);
+		}
+	}
+
+	// actions of the second and the main player in versus, a key with ctrl
+	// bound by one of them isn't taken by the other one as the key alone
+	pub fn versus(&self, code: KeyCode, modifiers: Modifiers) -> [Option<Msg>; 2] {
+		let key = Key::new(code, modifiers);
+		let exact = self.main.is_bound(key) || self.second.is_bound(key);
+
+		[&self.second, &self.main].map(|bindings| match exact {
+			true => bindings.find(key).map(|action| action.msg()),
+			false => bindings.action(code, modifiers),
+		})
+	}
+
+	// bindings of the player and of the other one
+	fn players(&mut self, second: bool) -> (&mut Bindings, &mut Bindings) {
+		match second {
+			true => (&mut self.second, &mut self.main),
+			false => (&mut self.main, &mut self.second),
+		}
+	}
+
+	// a broken file is left for the player to fix rather than overwritten
+	pub fn save(&self) -> io::Result<()> {
+		let path = Controls::path()
+			.ok_or_else(|| io::Error::new(io::ErrorKind::NotFound, "no config dir"))?;
+		if let Err(err) = Controls::read() {
+			return Err(io::Error::new(io::ErrorKind::InvalidData, format!("{err}, fix or remove it")));
+		}
+		let content = toml::to_string(self)
+			.map_err(|err| io::Error::new(io::ErrorKind::InvalidData, err))?;
+
+		if let Some(dir) = path.parent() {
+			fs::create_dir_all(dir)?;
+		}
+		fs::write(path, content)
+	}
+}
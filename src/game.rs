@@ -4,44 +4,416 @@ use {
 		canvas::event::{self, Event},
 		keyboard::{
 			KeyCode, Modifiers,
-			Event::KeyPressed,
+			Event::{
+				KeyPressed,
+				KeyReleased,
+			},
 		}, 
-		executor, time, window, Application, Color, Command, Container,
-		Element, Length, Point, Rectangle, Settings, Size, Subscription,
+		clipboard, executor, time, window, Application, Color, Command, Container,
+		Element, Length, Point, Rectangle, Size, Subscription,
 	},
 	crate::{
 		color::Color::*,
-		tetris::Tetris,
+		controls::Controls,
+		layout::{
+			self,
+			Layout,
+			Parts,
+		},
+		lobby::Lobby,
+		options::{
+			Change,
+			Options,
+		},
+		modes::{
+			Mode,
+			Visibility,
+		},
+		net::Online,
+		rebind::Rebind,
+		records::Records,
+		rollback::{
+			self,
+			Rollback,
+			UdpLink,
+			FRAME,
+		},
+		screens::{
+			self,
+			Choice,
+			Entry,
+			Menu,
+			Paused,
+			Screen,
+		},
+		settings::{
+			Settings,
+			Window,
+		},
+		theme::Theme,
+		tetris::{
+			Bot,
+			Coord,
+			Tetris,
+			Tile,
+		},
+		versus::Versus,
+	},
+	std::{
+		collections::HashSet,
+		time::{
+			Duration,
+			Instant,
+		},
 	},
 };
 
 
-#[derive(Debug, PartialEq, Clone, Copy)]
+// how often the clocks of boards are advanced
+const TICK: Duration = Duration::from_millis(10);
+// how often the bot makes a move
+const BOT_TICK: Duration = Duration::from_millis(40);
+
+
+#[derive(Debug, PartialEq, Clone)]
 pub enum Msg {
 	Proceed,
 	ShiftRgt,
 	ShiftLft,
 	Clockwise,
 	Counterclockwise,
+	Drop,
+	Hold,
+	// the board of the single player is copied as a fumen
+	Share,
+	Tick,
+	// a key no widget has taken, screens are navigated by it
+	Key(KeyCode, Modifiers),
+	// the settings screen
+	Setting(Change),
+	// lobby events
+	Address(String),
+	Host,
+	Join,
 	Exit,
 }
 
 
+// what is being played, there is only one game at a time
+#[allow(clippy::large_enum_variant)]
+pub enum Play {
+	Single(Tetris),
+	// the bot plays, the player watches
+	Watch(Tetris, Bot),
+	Versus(Versus),
+	Online(Online),
+	Rollback(Rollback<UdpLink>),
+	// single player modes with their own goals
+	Mode(Box<dyn Mode>),
+}
+
+
+// blocks of a board to be drawn
+type Tiles<'a> = Box<dyn Iterator<Item = (Coord<usize>, Tile)> + 'a>;
+
+
 pub struct Game {
-	tetris: Tetris,
+	// the top screen is shown, there is always at least one
+	screens: Vec<Screen>,
+	controls: Controls,
+	settings: Settings,
+	theme: Theme,
+	// keys held down, their actions and the time they have been held for
+	held: Vec<(KeyCode, Msg, Duration)>,
+	// local input of the current rollback frame
+	input: u8,
+	// when the clock of the mode was advanced last time
+	last: Instant,
+	// shown under the board until the next move, like that the board is shared
+	note: Option<String>,
+	exit: bool,
 	cache: Cache,
 }
 
+impl Game {
+	// modes are timed by the real clock, not by ticks,
+	// held keys are repeated by the same clock
+	fn advance(&mut self) {
+		let now = Instant::now();
+		let dt = now - self.last;
+		if let Some(Screen::Play(Play::Mode(mode), _)) = self.screens.last_mut() {
+			mode.tick(dt);
+		}
+		self.last = now;
+
+		for msg in self.repeats(dt) {
+			self.act(msg);
+		}
+	}
+
+	// the action of the key pressed by the player playing alone
+	fn act(&mut self, msg: Msg) {
+		match self.screens.last_mut() {
+			Some(Screen::Play(Play::Single(tetris), _)) => tetris.react_to(msg),
+			Some(Screen::Play(Play::Mode(mode), _)) => mode.react_to(msg),
+			_ => (),
+		}
+	}
+
+	// returns true if the key is already held, the keyboard repeats it itself
+	fn hold(&mut self, code: KeyCode, msg: &Msg) -> bool {
+		if !matches!(msg, Msg::ShiftLft | Msg::ShiftRgt | Msg::Proceed) {
+			return false;
+		}
+		if self.held.iter().any(|(held, _, _)| *held == code) {
+			return true;
+		}
+		self.held.push((code, msg.clone(), Duration::ZERO));
+		false
+	}
+
+	// actions of held keys that are due in the given time,
+	// shifts repeat after DAS every ARR, soft drop repeats SDF times faster than gravity
+	fn repeats(&mut self, dt: Duration) -> Vec<Msg> {
+		let tetris = match self.screens.last() {
+			Some(Screen::Play(Play::Single(tetris), _)) => tetris,
+			Some(Screen::Play(Play::Mode(mode), _)) => mode.tetris(),
+			_ => return vec!(),
+		};
+		let handling = &self.settings.handling;
+		let (das, arr) = (handling.das(), handling.arr());
+		let fall = tetris.level_time() / handling.sdf.max(1);
+		let width = tetris.width() as u128;
+
+		let due = |msg: &Msg, held: Duration| match msg {
+			Msg::Proceed => held.as_micros() / fall.as_micros().max(1),
+			_ => match held.checked_sub(das) {
+				None => 0,
+				Some(_) if arr.is_zero() => width,
+				Some(charged) => charged.as_micros() / arr.as_micros() + 1,
+			},
+		};
+
+		let mut repeats = vec!();
+		for (_, msg, held) in &mut self.held {
+			let count = due(msg, *held + dt) - due(msg, *held);
+			*held += dt;
+			repeats.extend(std::iter::repeat_n(msg.clone(), count.min(width) as usize));
+		}
+		repeats
+	}
+
+	// the game shown on the screen, it stays under the pause and the results
+	fn play(&self) -> Option<&Play> {
+		self.screens
+			.iter()
+			.rev()
+			.find(|screen| !matches!(screen, Screen::Pause(_) | Screen::Results(_)))
+			.and_then(|screen| match screen {
+				Screen::Play(play, _) => Some(play),
+				_ => None,
+			})
+	}
+
+	fn push(&mut self, screen: Screen) {
+		self.cache.clear();
+		self.note = None;
+		self.screens.push(screen);
+	}
+
+	// goes back to the screen under the top one
+	fn pop(&mut self) {
+		self.cache.clear();
+		self.note = None;
+		self.held.clear();
+		self.last = Instant::now();
+		self.screens.pop();
+	}
+
+	// screens are navigated by keys the canvas and widgets leave,
+	// the game itself is played by the keys the canvas gets
+	fn press(&mut self, code: KeyCode, modifiers: Modifiers) {
+		let back = code == KeyCode::Escape;
+		self.cache.clear();
+
+		match self.screens.last_mut() {
+			Some(Screen::Title) => match code {
+				KeyCode::Escape => self.exit = true,
+				KeyCode::Enter | KeyCode::Space =>
+					self.push(Screen::Menu(Menu::new("Tetris", Entry::ALL.to_vec()))),
+				_ => (),
+			},
+			Some(Screen::Menu(_)) | Some(Screen::Options(_)) | Some(Screen::Lobby(_))
+			| Some(Screen::Scores(_)) | Some(Screen::Modes(_)) if back => self.pop(),
+			Some(Screen::Menu(menu)) => {
+				if let Some(entry) = menu.press(code) {
+					self.enter(entry);
+				}
+			}
+			Some(Screen::Modes(menu)) => {
+				if let Some(choice) = menu.press(code) {
+					self.start(choice);
+				}
+			}
+			// the controls the game is played by are changed once it's left
+			Some(Screen::Rebind(_)) if back => {
+				self.pop();
+				self.controls = Controls::load();
+			}
+			Some(Screen::Rebind(rebind)) => rebind.press(code, modifiers),
+			// matches against remote players can't wait, they are left at once
+			Some(Screen::Play(Play::Online(_) | Play::Rollback(_), _)) if back => self.pop(),
+			Some(Screen::Play(_, choice)) if back => {
+				let mut entries = vec!(Paused::Resume, Paused::Quit);
+				if choice.is_some() {
+					entries.insert(1, Paused::Restart);
+				}
+				self.held.clear();
+				self.push(Screen::Pause(Menu::new("Paused", entries)));
+			}
+			Some(Screen::Pause(_)) if back => self.pop(),
+			Some(Screen::Pause(menu)) => match menu.press(code) {
+				Some(Paused::Resume) => self.pop(),
+				Some(Paused::Restart) => {
+					self.pop();
+					self.restart();
+				}
+				Some(Paused::Quit) => {
+					self.pop();
+					self.pop();
+				}
+				None => (),
+			},
+			Some(Screen::Results(_)) => match code {
+				KeyCode::Escape | KeyCode::Enter | KeyCode::Space => {
+					self.pop();
+					self.pop();
+				}
+				KeyCode::R => {
+					self.pop();
+					self.restart();
+				}
+				_ => (),
+			},
+			_ => (),
+		}
+	}
+
+	fn enter(&mut self, entry: Entry) {
+		match entry {
+			Entry::Play => self.start(Choice::Classic),
+			Entry::Modes => self.push(Screen::Modes(Menu::new("Modes", Choice::ALL.to_vec()))),
+			Entry::Online => self.push(Screen::Lobby(Lobby::new())),
+			Entry::Scores => self.push(Screen::Scores(screens::scores(Records::read()))),
+			Entry::Options => self.push(Screen::Options(Options::new(self.settings.clone()))),
+			Entry::Controls => self.push(Screen::Rebind(Rebind::new())),
+			Entry::Quit => self.exit = true,
+		}
+	}
+
+	// the game is started over the menu it's chosen in
+	fn start(&mut self, choice: Choice) {
+		match choice.play(&self.settings) {
+			Ok(play) => {
+				self.held.clear();
+				self.last = Instant::now();
+				self.push(Screen::Play(play, Some(choice)));
+			}
+			Err(err) => match self.screens.last_mut() {
+				Some(Screen::Menu(menu)) => menu.status = err,
+				Some(Screen::Modes(menu)) => menu.status = err,
+				_ => (),
+			},
+		}
+	}
+
+	// the game on the top is replaced by a new one of the same choice
+	fn restart(&mut self) {
+		if let Some(Screen::Play(_, Some(choice))) = self.screens.last() {
+			let choice = *choice;
+			self.pop();
+			self.start(choice);
+		}
+	}
+}
+
+
+// shown under the board once the game is over
+fn status(play: &Play) -> Option<String> {
+	match play {
+		Play::Single(tetris) | Play::Watch(tetris, _) if tetris.is_over() =>
+			Some(String::from("Game over")),
+		Play::Versus(versus) => match versus.winner() {
+			Some(0) => Some(String::from("Left player wins")),
+			Some(_) => Some(String::from("Right player wins")),
+			None if versus.is_over() => Some(String::from("Draw")),
+			None => None,
+		},
+		Play::Rollback(session) => match session.state().winner() {
+			_ if session.desync().is_some() =>
+				Some(format!("Desync on frame {}", session.desync().unwrap())),
+			Some(player) if player == session.local() =>
+				Some(String::from("You win")),
+			Some(_) => Some(String::from("You lose")),
+			None if session.state().is_over() => Some(String::from("Draw")),
+			None => None,
+		},
+		Play::Mode(mode) => mode.status(),
+		Play::Online(online) => match online.won() {
+			Some(true) => Some(String::from("You win")),
+			Some(false) => Some(String::from("You lose")),
+			None => None,
+		},
+		_ => None,
+	}
+}
+
+// lines of the results screen of the game that is over
+fn results(play: &Play, status: String, again: bool) -> Vec<String> {
+	let mut lines = vec!(status, String::new());
+	if let Play::Mode(mode) = play {
+		lines.extend(mode.hud());
+		lines.push(String::new());
+	}
+	lines.push(match again {
+		true => String::from("Enter for the menu, R to play again"),
+		false => String::from("Enter for the menu"),
+	});
+	lines
+}
+
 
 impl Application for Game {
 	type Executor = executor::Default;
 	type Message = Msg;
-	type Flags = ();
+	type Flags = Screen;
+
+	// the screen is opened over the menu, the title is the first one
+	fn new(screen: Screen) -> (Self, Command<Msg>) {
+		let mut screens = vec!(Screen::Title);
+		if !matches!(screen, Screen::Title) {
+			screens.push(Screen::Menu(Menu::new("Tetris", Entry::ALL.to_vec())));
+			screens.push(screen);
+		}
+
+		// the classic theme is used if the one of the settings can't be loaded
+		let settings = Settings::load();
+		let theme = Theme::load(&settings.theme).unwrap_or_else(|err| {
+			eprintln!("{err}");
+			Theme::default()
+		});
 
-	fn new(_flags: ()) -> (Self, Command<Msg>) {
 		(
 			Self {
-				tetris: Tetris::new(),
+				screens,
+				controls: Controls::load(),
+				settings,
+				theme,
+				held: vec!(),
+				input: 0,
+				last: Instant::now(),
+				note: None,
+				exit: false,
 				cache: Default::default(),
 			},
 			Command::none(),
@@ -49,31 +421,151 @@ impl Application for Game {
 	}
 
 	fn mode(&self) -> window::Mode {
-		window::Mode::Fullscreen
+		match self.settings.display.window {
+			Window::Windowed | Window::Undecorated => window::Mode::Windowed,
+			Window::Fullscreen => window::Mode::Fullscreen,
+		}
 	}
 
 	fn title(&self) -> String {
 		String::from("Tetris")
 	}
 
+	fn should_exit(&self) -> bool {
+		self.exit
+	}
+
 	fn update(&mut self, msg: Msg) -> Command<Msg> {
-		if msg == Msg::Proceed {
-			self.cache.clear();
-			self.tetris.proceed();
+		let mut command = Command::none();
+
+		match (self.screens.last_mut(), msg) {
+			(_, Msg::Key(code, modifiers)) => self.press(code, modifiers),
+			// the board is copied without the alive tetrimino
+			(Some(Screen::Play(play, _)), Msg::Share) => {
+				let single = match play {
+					Play::Single(tetris) | Play::Watch(tetris, _) => Some(&*tetris),
+					Play::Mode(mode) => Some(mode.tetris()),
+					_ => None,
+				};
+				self.cache.clear();
+				match single.map(Tetris::fumen) {
+					Some(Ok(fumen)) => {
+						self.note = Some(String::from("The board is copied as a fumen"));
+						command = clipboard::write(fumen.to_string());
+					}
+					Some(Err(err)) => self.note = Some(format!("Unable to share the board: {err}")),
+					None => (),
+				}
+			}
+			(Some(Screen::Play(play, _)), msg) => match (play, msg) {
+				(Play::Single(tetris), Msg::Proceed) => {
+					self.cache.clear();
+					tetris.proceed();
+				}
+				(Play::Watch(tetris, bot), Msg::Tick) => {
+					self.cache.clear();
+					bot.play(tetris);
+					// gravity would move the tetrimino off the planned path
+					if !bot.busy() {
+						tetris.tick(BOT_TICK);
+					}
+				}
+				(Play::Versus(versus), Msg::Tick) => {
+					self.cache.clear();
+					versus.tick(TICK);
+				}
+				(Play::Online(online), Msg::Tick) => {
+					self.cache.clear();
+					online.tick(TICK);
+				}
+				(Play::Rollback(session), Msg::Tick) => {
+					self.cache.clear();
+					if session.update(self.input) {
+						self.input = 0;
+					}
+				}
+				(Play::Single(_) | Play::Mode(_), Msg::Tick) => {
+					self.cache.clear();
+					self.advance();
+				}
+				_ => (),
+			},
+			(Some(Screen::Options(options)), Msg::Setting(change)) => {
+				let resize = match change {
+					Change::Resolution(size) => Some(size),
+					_ => None,
+				};
+				let theme = matches!(change, Change::Theme(_));
+				options.update(change);
+				self.settings = options.settings().clone();
+
+				if let (true, Ok(loaded)) = (theme, Theme::load(&self.settings.theme)) {
+					self.theme = loaded;
+				}
+
+				if let Some(size) = resize {
+					return window::resize(size.width, size.height);
+				}
+			}
+			(Some(Screen::Lobby(lobby)), msg) => {
+				if let Some(online) = lobby.update(msg) {
+					self.screens.pop();
+					self.push(Screen::Play(Play::Online(online), None));
+				}
+			}
+			_ => (),
+		}
+
+		// the results are shown over the game once it's over
+		if let Some(Screen::Play(play, choice)) = self.screens.last() {
+			if let Some(status) = status(play) {
+				let lines = results(play, status, choice.is_some());
+				self.held.clear();
+				self.push(Screen::Results(lines));
+			}
 		}
-        Command::none()
+
+		command
 	}
 
+	// keys of all screens and the clock of the top one
 	fn subscription(&self) -> Subscription<Msg> {
-        time::every(self.tetris.level_time()).map(|_| {
-            Msg::Proceed
-        })
+		let keys = iced_native::subscription::events_with(|event, status| match (event, status) {
+			(iced_native::Event::Keyboard(KeyPressed { key_code, modifiers }), event::Status::Ignored) =>
+				Some(Msg::Key(key_code, modifiers)),
+			_ => None,
+		});
+
+		let clock = match self.screens.last() {
+			Some(Screen::Play(Play::Single(tetris), _)) => Subscription::batch([
+				time::every(tetris.level_time()).map(|_| Msg::Proceed),
+				time::every(TICK).map(|_| Msg::Tick),
+			]),
+			Some(Screen::Play(Play::Watch(..), _)) =>
+				time::every(BOT_TICK).map(|_| Msg::Tick),
+			Some(Screen::Play(Play::Versus(_) | Play::Online(_) | Play::Mode(_), _)) | Some(Screen::Lobby(_)) =>
+				time::every(TICK).map(|_| Msg::Tick),
+			Some(Screen::Play(Play::Rollback(_), _)) =>
+				time::every(FRAME).map(|_| Msg::Tick),
+			_ => Subscription::none(),
+		};
+
+		Subscription::batch([keys, clock])
     }
 
-	fn view(&mut self) -> Element<Msg> {
-		let canvas = Canvas::new(self).width(Length::Fill).height(Length::Fill);
+	fn view(&mut self) -> Element<'_, Msg> {
+		let content =
+			if !matches!(self.screens.last(), Some(Screen::Lobby(_) | Screen::Options(_))) {
+				Canvas::new(self).width(Length::Fill).height(Length::Fill).into()
+			} else {
+				match self.screens.last_mut() {
+					Some(Screen::Lobby(lobby)) => lobby.view(),
+					Some(Screen::Options(options)) => options.view(),
+					_ => unreachable!(),
+				}
+			};
 
-        Container::new(canvas)
+        Container::new(content)
             .width(Length::Fill)
             .height(Length::Fill)
             .padding(20)
@@ -81,35 +573,69 @@ impl Application for Game {
 	}
 }
 
-impl<Message> canvas::Program<Message> for Game {
+impl canvas::Program<Msg> for Game {
 	fn update(&mut self,
               ev: Event,
-              _: Rectangle, _: Cursor, ) -> (event::Status, Option<Message>) {
+              _: Rectangle, _: Cursor, ) -> (event::Status, Option<Msg>) {
 
-		if let Event::Keyboard(KeyPressed{ key_code, modifiers }) = ev {
-			match (key_code, modifiers.control()) {
-				(KeyCode::Right, false) => {
-					self.cache.clear();
-					self.tetris.react_to(Msg::ShiftRgt);
+		let play = match self.screens.last_mut() {
+			Some(Screen::Play(play, _)) => play,
+			_ => return (event::Status::Ignored, None),
+		};
+
+		// modes with auto shift need to know when shifting stops, the key
+		// is looked up as it was pressed since ctrl may be let go before it
+		if let Event::Keyboard(KeyReleased{ key_code, .. }) = ev {
+			if let Some(i) = self.held.iter().position(|(held, _, _)| *held == key_code) {
+				self.advance();
+				let (_, msg, _) = self.held.remove(i);
+				if let Some(Screen::Play(Play::Mode(mode), _)) = self.screens.last_mut() {
+					mode.release(msg);
 				}
-				(KeyCode::Left, false) => {
-					self.cache.clear();
-					self.tetris.react_to(Msg::ShiftLft);
+			}
+			return (event::Status::Ignored, None);
+		}
+
+		if let Event::Keyboard(KeyPressed{ key_code, modifiers }) = ev {
+			// the board is shared by the app, it can't be copied from here
+			if self.controls.main.action(key_code, modifiers) == Some(Msg::Share) {
+				return (event::Status::Ignored, Some(Msg::Share));
+			}
+
+			match play {
+				Play::Versus(versus) => {
+					let actions = self.controls.versus(key_code, modifiers);
+					for (player, action) in actions.into_iter().enumerate() {
+						// boards of two players aren't shared
+						if let Some(msg) = action.filter(|msg| *msg != Msg::Share) {
+							self.cache.clear();
+							versus.react_to(player, msg);
+						}
+					}
 				}
-				(KeyCode::Right, true) => {
-					self.cache.clear();
-					self.tetris.react_to(Msg::Clockwise);
+				Play::Online(online) => {
+					if let Some(msg) = self.controls.main.action(key_code, modifiers) {
+						self.cache.clear();
+						online.react_to(msg);
+					}
 				}
-				(KeyCode::Left, true) => {
-					self.cache.clear();
-					self.tetris.react_to(Msg::Counterclockwise);
+				Play::Rollback(_) => {
+					if let Some(msg) = self.controls.main.action(key_code, modifiers) {
+						self.input = rollback::input_with(self.input, &msg);
+					}
 				}
-				(KeyCode::Down, _) => {
-					self.cache.clear();
-					self.tetris.proceed();
+				Play::Single(_) | Play::Mode(_) => {
+					if let Some(msg) = self.controls.main.action(key_code, modifiers) {
+						self.cache.clear();
+						self.note = None;
+						// the clock is brought up to the moment of the key press
+						self.advance();
+						if !self.hold(key_code, &msg) {
+							self.act(msg);
+						}
+					}
 				}
-				(KeyCode::Escape, _) => std::process::exit(0),
-				_ => (),
+				Play::Watch(..) => (),
 			};
 		}
 
@@ -117,16 +643,191 @@ impl<Message> canvas::Program<Message> for Game {
 	}
 
 	fn draw(&self, bounds: Rectangle, _cursor: Cursor) -> Vec<Geometry> {
-		let side = bounds.size().height * 0.8 / 16.0;
-		let size = Size { width: side, height: side };
-
+		let play = self.play();
+		let single = match play {
+			Some(Play::Single(tetris) | Play::Watch(tetris, _)) => Some(tetris),
+			Some(Play::Mode(mode)) => Some(mode.tetris()),
+			_ => None,
+		};
 		let cache = self.cache.draw(bounds.size(), |frame| {
-			let map = self.tetris.map_iter();
-			for (coord, color) in map {
-				let point = Point::new(side * (coord.x as f32),
-									   side * (16.0 - coord.y as f32));
-            	let background = Path::rectangle(point, size);
-            	frame.fill(&background, color.to_rgb());
+			let boards: Vec<Tiles> = match play {
+				Some(Play::Single(tetris) | Play::Watch(tetris, _)) =>
+					vec!(Box::new(tetris.map_iter())),
+				Some(Play::Mode(mode)) => vec!(Box::new(mode.tetris().map_iter())),
+				Some(Play::Versus(versus)) => versus
+					.boards()
+					.iter()
+					.map(|tetris| Box::new(tetris.map_iter()) as Tiles)
+					.collect(),
+				Some(Play::Online(online)) => vec!(
+					Box::new(online.board().map_iter()),
+					Box::new(online.remote_iter()),
+				),
+				Some(Play::Rollback(session)) => session
+					.state()
+					.boards()
+					.iter()
+					.map(|tetris| Box::new(tetris.map_iter()) as Tiles)
+					.collect(),
+				None => vec!(),
+			};
+
+			let measure = |tetris: &Tetris| (tetris.width(), tetris.height());
+			let (cols, rows) = match play {
+				Some(Play::Versus(versus)) => measure(&versus.boards()[0]),
+				Some(Play::Online(online)) => measure(online.board()),
+				Some(Play::Rollback(session)) => measure(&session.state().boards()[0]),
+				_ => single.map_or((0, 0), measure),
+			};
+			let hud = match play {
+				Some(Play::Mode(mode)) => mode.hud(),
+				_ => vec!(),
+			};
+
+			let layout = Layout::new(bounds.size(), Parts {
+				boards: boards.len(),
+				cols,
+				rows,
+				slots: single.map_or(0, |tetris| tetris.preview().len() + 2),
+				hud: (hud.len(), hud.iter().map(|line| line.chars().count()).max().unwrap_or(0)),
+			});
+			let side = layout.side;
+			let size = Size { width: side, height: side };
+			// top left corner of the block on the board
+			let corner = |board: usize, x: usize, y: usize| Point::new(
+				layout.boards[board].x + side * x as f32,
+				layout.boards[board].y + side * (rows - 1 - y) as f32,
+			);
+
+			// modes may hide locked blocks, they are faded into the background
+			let (visibility, clock) = match play {
+				Some(Play::Mode(mode)) => (mode.visibility(), mode.tetris().clock()),
+				_ => (Visibility::Visible, Duration::ZERO),
+			};
+
+			for (i, tiles) in boards.into_iter().enumerate() {
+				for (coord, tile) in tiles {
+					let background = Path::rectangle(corner(i, coord.x, coord.y), size);
+					let alpha = tile.locked
+						.map_or(1.0, |locked| visibility.alpha(clock.saturating_sub(locked)));
+
+					if alpha < 1.0 {
+						frame.fill(&background, self.theme.color(Tile::from(Non)));
+					}
+					frame.fill(&background, Color { a: alpha, ..self.theme.color(tile) });
+				}
+			}
+
+			// edges between the locked blocks and the rest of the board
+			if let Some(Play::Mode(mode)) = play {
+				if mode.outline() {
+					let stack: HashSet<(i64, i64)> = mode
+						.tetris()
+						.map_iter()
+						.filter(|(_, tile)| tile.locked.is_some())
+						.map(|(coord, _)| (coord.x as i64, coord.y as i64))
+						.collect();
+					let stroke = Stroke {
+						color: Color::WHITE,
+						width: side * 0.1,
+						line_cap: LineCap::Square,
+						..Default::default()
+					};
+
+					for &(x, y) in &stack {
+						let Point { x: left, y: top } = corner(0, x as usize, y as usize);
+						let (right, bottom) = (left + side, top + side);
+						let edges = [
+							((0, 1), (left, top), (right, top)),
+							((0, -1), (left, bottom), (right, bottom)),
+							((-1, 0), (left, top), (left, bottom)),
+							((1, 0), (right, top), (right, bottom)),
+						];
+
+						for ((dx, dy), from, to) in edges {
+							if !stack.contains(&(x + dx, y + dy)) {
+								let edge = Path::line(Point::new(from.0, from.1), Point::new(to.0, to.1));
+								frame.stroke(&edge, stroke);
+							}
+						}
+					}
+				}
+			}
+
+			// next tetriminos and the held one are shown to the right
+			if let Some(tetris) = single {
+				let small = side * 0.5;
+				let size = Size { width: small, height: small };
+
+				let next = tetris.preview().into_iter().map(Some);
+				let shapes = next.chain([None, tetris.held()]).enumerate();
+
+				for (i, id) in shapes {
+					let (blocks, tile) = match id {
+						Some(id) => tetris.shape_of(id),
+						None => continue,
+					};
+
+					for block in blocks {
+						let point = Point::new(layout.preview.x + small * block.x as f32,
+											   layout.preview.y + side * layout::SLOT * (i as f32 + 0.5) - small * block.y as f32);
+						frame.fill(&Path::rectangle(point, size), self.theme.color(tile));
+					}
+				}
+			}
+
+			// goals and timers of the mode are shown next to the preview or under the board
+			for (i, content) in hud.into_iter().enumerate() {
+				frame.fill_text(canvas::Text {
+					content,
+					position: Point::new(layout.hud.x, layout.hud.y + side * layout::HUD_LINE * i as f32),
+					color: Color::WHITE,
+					size: side * layout::HUD_FONT,
+					..Default::default()
+				});
+			}
+
+			// long ones are made smaller so that they aren't cut
+			if let Some(content) = play.and_then(status).or_else(|| self.note.clone()) {
+				let width = content.chars().count() as f32 * layout::CHAR;
+				frame.fill_text(canvas::Text {
+					position: layout.status,
+					color: Color::WHITE,
+					size: side.min(layout.width / width.max(1.0)),
+					content,
+					..Default::default()
+				});
+			}
+
+			// menus are written over the game they pause
+			let lines = match self.screens.last() {
+				Some(Screen::Title) => vec!(
+					String::from("Tetris"),
+					String::new(),
+					String::from("Press Enter"),
+				),
+				Some(Screen::Menu(menu)) => menu.lines(),
+				Some(Screen::Modes(menu)) => menu.lines(),
+				Some(Screen::Pause(menu)) => menu.lines(),
+				Some(Screen::Rebind(rebind)) => rebind.lines(),
+				Some(Screen::Scores(lines) | Screen::Results(lines)) => lines.clone(),
+				_ => vec!(),
+			};
+
+			if play.is_some() && !lines.is_empty() {
+				let shade = Path::rectangle(Point::ORIGIN, frame.size());
+				frame.fill(&shade, Color { a: 0.8, ..Color::BLACK });
+			}
+
+			let size = layout::font(bounds.size(), &lines);
+			for (i, content) in lines.into_iter().enumerate() {
+				frame.fill_text(canvas::Text {
+					content,
+					position: layout::line(size, i),
+					color: Color::WHITE,
+					size,
+					..Default::default()
+				});
 			}
 		});
 
@@ -0,0 +1,264 @@
+use iced::{
+	Point,
+	Size,
+};
+
+
+// rough width of a character in sizes of the font, text is measured by it
+pub const CHAR: f32 = 0.55;
+// text of the menus isn't made any larger
+const MAX_FONT: f32 = 36.0;
+// lines of text are this many sizes of the font apart
+const LINE: f32 = 1.4;
+
+// blocks between boards and panels
+const GAP: f32 = 1.0;
+// height of a slot of the queue, shapes are drawn at half the size of blocks
+pub const SLOT: f32 = 2.5;
+const SLOT_WIDTH: f32 = 2.0;
+// text of the HUD is this many blocks large and this far apart
+pub const HUD_FONT: f32 = 0.6;
+pub const HUD_LINE: f32 = 0.8;
+// height of the status under the boards
+const STATUS: f32 = 2.0;
+
+
+// what is shown for a game, everything is measured in blocks of the boards
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub struct Parts {
+	pub boards: usize,
+	pub cols: usize,
+	pub rows: usize,
+	// slots of the queue and the held tetrimino, zero if they aren't shown
+	pub slots: usize,
+	// lines of the HUD and the length of the longest one
+	pub hud: (usize, usize),
+}
+
+// where the parts are drawn, in pixels
+#[derive(Debug, Clone, PartialEq)]
+pub struct Layout {
+	// side of a block
+	pub side: f32,
+	// top left corners of the boards
+	pub boards: Vec<Point>,
+	pub preview: Point,
+	pub hud: Point,
+	pub status: Point,
+	// width the status may take
+	pub width: f32,
+}
+
+impl Layout {
+	// panels are put next to the boards or under them, whatever makes blocks larger,
+	// and the whole of it is centered
+	pub fn new(bounds: Size, parts: Parts) -> Layout {
+		// there is nothing to fit without a board
+		if parts.boards == 0 || parts.cols == 0 || parts.rows == 0 {
+			let center = Point::new(bounds.width / 2.0, bounds.height / 2.0);
+			return Layout {
+				side: 0.0,
+				boards: vec!(),
+				preview: center,
+				hud: center,
+				status: center,
+				width: 0.0,
+			};
+		}
+
+		let fit = |size: Size| (bounds.width / size.width).min(bounds.height / size.height);
+		let (wide, tall) = (Layout::wide(parts), Layout::tall(parts));
+
+		let (units, size) = if fit(wide.1) >= fit(tall.1) { wide } else { tall };
+		let side = fit(size);
+		let origin = Point::new(
+			(bounds.width - size.width * side) / 2.0,
+			(bounds.height - size.height * side) / 2.0,
+		);
+		let scale = |point: Point| Point::new(origin.x + point.x * side, origin.y + point.y * side);
+
+		Layout {
+			side,
+			boards: units.boards.into_iter().map(scale).collect(),
+			preview: scale(units.preview),
+			hud: scale(units.hud),
+			status: scale(units.status),
+			width: size.width * side,
+		}
+	}
+
+	// boards side by side, the queue and the HUD to the right of them
+	fn wide(parts: Parts) -> (Layout, Size) {
+		let (cols, rows) = (parts.cols as f32, parts.rows as f32);
+		let (lines, longest) = (parts.hud.0 as f32, parts.hud.1 as f32);
+
+		let boards = (0..parts.boards)
+			.map(|i| Point::new(i as f32 * (cols + 2.0 * GAP), 0.0))
+			.collect();
+		let mut right = parts.boards as f32 * (cols + 2.0 * GAP) - 2.0 * GAP;
+
+		let preview = Point::new(right + GAP, 0.0);
+		if parts.slots > 0 {
+			right += GAP + SLOT_WIDTH;
+		}
+
+		let hud = Point::new(right + GAP, 0.0);
+		if parts.hud.0 > 0 {
+			right += GAP + longest * HUD_FONT * CHAR;
+		}
+
+		let height = (rows + STATUS)
+			.max(parts.slots as f32 * SLOT)
+			.max(lines * HUD_LINE);
+		let layout = Layout {
+			side: 1.0,
+			boards,
+			preview,
+			hud,
+			status: Point::new(0.0, rows + 0.5),
+			width: right,
+		};
+		(layout, Size::new(right, height))
+	}
+
+	// boards one under another with the queue to the right of the first one,
+	// the HUD goes under all of them
+	fn tall(parts: Parts) -> (Layout, Size) {
+		let (cols, rows) = (parts.cols as f32, parts.rows as f32);
+		let (lines, longest) = (parts.hud.0 as f32, parts.hud.1 as f32);
+
+		let boards = (0..parts.boards)
+			.map(|i| Point::new(0.0, i as f32 * (rows + 2.0 * GAP)))
+			.collect();
+		let bottom = parts.boards as f32 * (rows + 2.0 * GAP) - 2.0 * GAP;
+
+		let mut width = cols;
+		let preview = Point::new(cols + GAP, 0.0);
+		if parts.slots > 0 {
+			width += GAP + SLOT_WIDTH;
+		}
+
+		let status = Point::new(0.0, bottom + 0.5);
+		let top = (bottom + STATUS).max(parts.slots as f32 * SLOT);
+		let hud = Point::new(0.0, top);
+		width = width.max(longest * HUD_FONT * CHAR);
+
+		let layout = Layout {
+			side: 1.0,
+			boards,
+			preview,
+			hud,
+			status,
+			width,
+		};
+		(layout, Size::new(width, top + lines * HUD_LINE))
+	}
+}
+
+
+// size of the font the lines of text fit the bounds with
+pub fn font(bounds: Size, lines: &[String]) -> f32 {
+	let longest = lines.iter().map(|line| line.chars().count()).max().unwrap_or(0);
+	let by_height = bounds.height / ((lines.len() as f32 + 2.0) * LINE);
+	let by_width = bounds.width / (longest as f32 * CHAR + 2.0 * LINE);
+	by_height.min(by_width).min(MAX_FONT)
+}
+
+// top left corner of the given line written in the given size
+pub fn line(size: f32, i: usize) -> Point {
+	Point::new(size * LINE, size * LINE * (i + 1) as f32)
+}
+
+
+#[cfg(test)]
+mod tests {
+	use super::*;
+
+	const SINGLE: Parts = Parts { boards: 1, cols: 10, rows: 16, slots: 6, hud: (4, 12) };
+	const VERSUS: Parts = Parts { boards: 2, cols: 10, rows: 16, slots: 0, hud: (0, 0) };
+	// master shows ten lines of timings
+	const HUD: Parts = Parts { boards: 1, cols: 10, rows: 20, slots: 6, hud: (30, 24) };
+
+	const LANDSCAPE: Size = Size { width: 1280.0, height: 720.0 };
+	const PORTRAIT: Size = Size { width: 480.0, height: 1000.0 };
+	const SQUARE: Size = Size { width: 600.0, height: 600.0 };
+	const STRIP: Size = Size { width: 2000.0, height: 150.0 };
+
+	// rounding of the centering is forgiven
+	fn inside(bounds: Size, left: f32, top: f32, width: f32, height: f32) -> bool {
+		let eps = 0.01;
+		left >= -eps && top >= -eps &&
+		left + width <= bounds.width + eps && top + height <= bounds.height + eps
+	}
+
+	fn assert_fits(bounds: Size, parts: Parts) {
+		let layout = Layout::new(bounds, parts);
+		let side = layout.side;
+		let (cols, rows) = (parts.cols as f32, parts.rows as f32);
+		let case = format!("{parts:?} in {bounds:?}: {layout:?}");
+
+		assert!(side > 0.0, "{case}");
+		assert_eq!(layout.boards.len(), parts.boards, "{case}");
+		for board in &layout.boards {
+			assert!(inside(bounds, board.x, board.y, cols * side, rows * side), "{case}");
+		}
+
+		if parts.slots > 0 {
+			let preview = layout.preview;
+			let height = parts.slots as f32 * SLOT * side;
+			assert!(inside(bounds, preview.x, preview.y, SLOT_WIDTH * side, height), "{case}");
+		}
+
+		if parts.hud.0 > 0 {
+			let hud = layout.hud;
+			let width = parts.hud.1 as f32 * HUD_FONT * CHAR * side;
+			let height = parts.hud.0 as f32 * HUD_LINE * side;
+			assert!(inside(bounds, hud.x, hud.y, width, height), "{case}");
+		}
+
+		let status = layout.status;
+		assert!(inside(bounds, status.x, status.y, layout.width, (STATUS - 0.5) * side), "{case}");
+	}
+
+	#[test]
+	fn landscape() {
+		for parts in [SINGLE, VERSUS, HUD] {
+			assert_fits(LANDSCAPE, parts);
+			assert_fits(STRIP, parts);
+		}
+
+		let layout = Layout::new(LANDSCAPE, VERSUS);
+		assert_eq!(layout.boards[0].y, layout.boards[1].y);
+	}
+
+	#[test]
+	fn portrait() {
+		for parts in [SINGLE, VERSUS, HUD] {
+			assert_fits(PORTRAIT, parts);
+			assert_fits(SQUARE, parts);
+		}
+
+		// boards go one under another rather than shrinking side by side
+		let layout = Layout::new(PORTRAIT, VERSUS);
+		assert_eq!(layout.boards[0].x, layout.boards[1].x);
+		assert!(layout.boards[1].y > layout.boards[0].y);
+	}
+
+	#[test]
+	fn large_hud() {
+		let wide = Parts { hud: (4, 80), ..SINGLE };
+		for bounds in [LANDSCAPE, PORTRAIT, SQUARE, STRIP] {
+			assert_fits(bounds, HUD);
+			assert_fits(bounds, wide);
+		}
+	}
+
+	#[test]
+	fn nothing_to_show() {
+		let empty = Parts { boards: 0, cols: 0, rows: 0, slots: 0, hud: (0, 0) };
+		let layout = Layout::new(LANDSCAPE, empty);
+		assert_eq!(layout.side, 0.0);
+		assert!(layout.boards.is_empty());
+		assert!(inside(LANDSCAPE, layout.status.x, layout.status.y, layout.width, 0.0));
+	}
+}
@@ -0,0 +1,16 @@
+pub mod color;
+pub mod controls;
+pub mod game;
+pub mod layout;
+pub mod lobby;
+pub mod modes;
+pub mod net;
+pub mod options;
+pub mod rebind;
+pub mod records;
+pub mod rollback;
+pub mod screens;
+pub mod settings;
+pub mod tetris;
+pub mod theme;
+pub mod versus;
@@ -0,0 +1,104 @@
+use {
+	iced::{
+		button, text_input, Alignment, Button, Column, Element, Length, Row,
+		Text, TextInput,
+	},
+	crate::{
+		game::Msg,
+		net::{
+			Online,
+			Peer,
+			DEFAULT_ADDR,
+		},
+	},
+	std::{
+		io,
+		sync::mpsc::Receiver,
+	},
+};
+
+
+// screen where the player hosts a match or joins one by address
+pub struct Lobby {
+	addr: String,
+	status: String,
+	// connection that is being established in the background
+	pending: Option<Receiver<io::Result<Peer>>>,
+	addr_state: text_input::State,
+	host_state: button::State,
+	join_state: button::State,
+}
+
+impl Default for Lobby {
+	fn default() -> Lobby {
+		Lobby::new()
+	}
+}
+
+impl Lobby {
+	pub fn new() -> Lobby {
+		Lobby {
+			addr: String::from(DEFAULT_ADDR),
+			status: String::new(),
+			pending: None,
+			addr_state: text_input::State::focused(),
+			host_state: button::State::new(),
+			join_state: button::State::new(),
+		}
+	}
+
+	// returns the match once the opponent is connected
+	pub fn update(&mut self, msg: Msg) -> Option<Online> {
+		match msg {
+			Msg::Address(addr) => self.addr = addr,
+			Msg::Host if self.pending.is_none() => {
+				self.status = format!("Waiting for the opponent on {}", self.addr);
+				self.pending = Some(Peer::connect(true, self.addr.clone()));
+			}
+			Msg::Join if self.pending.is_none() => {
+				self.status = format!("Joining {}", self.addr);
+				self.pending = Some(Peer::connect(false, self.addr.clone()));
+			}
+			Msg::Tick => {
+				let peer = self.pending.as_ref()?.try_recv().ok()?;
+				self.pending = None;
+
+				match peer {
+					Ok(peer) => return Some(Online::new(peer)),
+					Err(err) => self.status = err.to_string(),
+				}
+			}
+			_ => (),
+		};
+
+		None
+	}
+
+	pub fn view(&mut self) -> Element<'_, Msg> {
+		let addr = TextInput::new(
+				&mut self.addr_state, DEFAULT_ADDR, &self.addr, Msg::Address)
+			.padding(10)
+			.size(20)
+			.on_submit(Msg::Join);
+
+		let buttons = Row::new()
+			.spacing(20)
+			.push(Button::new(&mut self.host_state, Text::new("Host"))
+				.padding(10)
+				.on_press(Msg::Host))
+			.push(Button::new(&mut self.join_state, Text::new("Join"))
+				.padding(10)
+				.on_press(Msg::Join));
+
+		Column::new()
+			.width(Length::Units(400))
+			.spacing(20)
+			.align_items(Alignment::Center)
+			.push(Text::new("Online match").size(40))
+			.push(addr)
+			.push(buttons)
+			.push(Text::new(&self.status))
+			.push(Text::new("Escape to go back"))
+			.into()
+	}
+}
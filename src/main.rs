@@ -1,19 +1,180 @@
 #![allow(warnings)]
 
-mod color;
-mod game;
-mod tetris;
-
 use {
-	color::Color,
-	game::Game,
-	tetris::Tetris,
-	iced::{window, Application, Settings},
+	tetris::{
+		game::{Game, Play},
+		lobby::Lobby,
+		modes::{self, Dig, Hidden, Marathon, Master, Puzzle, Sprint, Ultra},
+		net::{Online, Peer, DEFAULT_ADDR},
+		options::Options,
+		rebind::Rebind,
+		rollback::{self, Rollback, UdpLink},
+		screens::Screen,
+		settings::{Settings, Window},
+		tetris::{Bot, Fumen, Ruleset, Setup, Tetris, Weights},
+		theme::Theme,
+		versus::Versus,
+	},
+	iced::{window, Application},
 };
 
 
 fn main() -> iced::Result {
-	Game::run(Settings::default())
+	// the game is chosen by the first argument, the title screen is shown without one
+	let args: Vec<String> = std::env::args().collect();
+	let addr = args.get(2).map(String::as_str).unwrap_or(DEFAULT_ADDR);
+
+	let play = match args.get(1).map(String::as_str) {
+		Some("versus") => Play::Versus(Versus::new()),
+		// ai ["height lines holes bumpiness wells"]
+		Some("ai") => {
+			let weights = match args.get(2) {
+				Some(weights) => weights.parse().unwrap_or_else(|err| {
+					eprintln!("Invalid weights: {err}");
+					std::process::exit(1);
+				}),
+				None => Weights::default(),
+			};
+			Play::Watch(Tetris::new(), Bot::new(weights))
+		}
+		// rules <ruleset file> [seed]
+		Some("rules") => {
+			let path = args.get(2).map(String::as_str).unwrap_or("rules.toml");
+			let seed = args.get(3).and_then(|arg| arg.parse().ok()).unwrap_or_else(rand::random);
+
+			let ruleset = Ruleset::load(path.as_ref()).unwrap_or_else(|err| {
+				eprintln!("{path}: {err}");
+				std::process::exit(1);
+			});
+			Play::Single(Tetris::with(ruleset.setup(seed)))
+		}
+		// sprint [lines] [seed]
+		Some("sprint") => {
+			let lines = args.get(2).and_then(|arg| arg.parse().ok()).unwrap_or(modes::sprint::TARGET);
+			let seed = args.get(3).and_then(|arg| arg.parse().ok()).unwrap_or_else(rand::random);
+			Play::Mode(Box::new(Sprint::new(lines, Setup { seed, ..Setup::default() })))
+		}
+		// ultra [minutes] [seed]
+		Some("ultra") => {
+			let limit = args.get(2).map_or(modes::ultra::LIMIT, |arg| minutes(arg));
+			let seed = args.get(3).and_then(|arg| arg.parse().ok()).unwrap_or_else(rand::random);
+			Play::Mode(Box::new(Ultra::new(limit, Setup { seed, ..Setup::default() })))
+		}
+		// marathon [lines or endless] [seed]
+		Some("marathon") => {
+			let goal = match args.get(2).map(String::as_str) {
+				Some("endless") => None,
+				arg => Some(arg.and_then(|arg| arg.parse().ok()).unwrap_or(modes::marathon::GOAL)),
+			};
+			let seed = args.get(3).and_then(|arg| arg.parse().ok()).unwrap_or_else(rand::random);
+			Play::Mode(Box::new(Marathon::new(goal, Setup { seed, ..Setup::default() })))
+		}
+		// puzzle <puzzle file>
+		Some("puzzle") => {
+			let path = args.get(2).map(String::as_str).unwrap_or("puzzle.toml");
+			let puzzle = Puzzle::load(path.as_ref()).unwrap_or_else(|err| {
+				eprintln!("{path}: {err}");
+				std::process::exit(1);
+			});
+			Play::Mode(Box::new(puzzle))
+		}
+		// dig [rows] [seed], survival [seconds between rows] [seed]
+		Some("dig") => {
+			let rows = args.get(2).and_then(|arg| arg.parse().ok()).unwrap_or(modes::dig::ROWS);
+			let seed = args.get(3).and_then(|arg| arg.parse().ok()).unwrap_or_else(rand::random);
+			Play::Mode(Box::new(Dig::new(rows, None, Setup { seed, ..Setup::default() })))
+		}
+		Some("survival") => {
+			let interval = args.get(2).map_or(modes::dig::INTERVAL, |arg| seconds(arg));
+			let seed = args.get(3).and_then(|arg| arg.parse().ok()).unwrap_or_else(rand::random);
+			Play::Mode(Box::new(Dig::new(modes::dig::ROWS, Some(interval), Setup { seed, ..Setup::default() })))
+		}
+		// fumen DATA [seed], the board of the first page is played on
+		Some("fumen") => {
+			let data = args.get(2).map(String::as_str).unwrap_or_default();
+			let seed = args.get(3).and_then(|arg| arg.parse().ok()).unwrap_or_else(rand::random);
+			let mut tetris = Tetris::seeded(seed);
+
+			let loaded = data
+				.parse::<Fumen>()
+				.map_err(|err| err.to_string())
+				.and_then(|fumen| match fumen.pages.first() {
+					Some(page) => tetris.load_map(&page.board()).map_err(|err| err.to_string()),
+					None => Err(String::from("there are no pages")),
+				});
+			if let Err(err) = loaded {
+				eprintln!("{data}: {err}");
+				std::process::exit(1);
+			}
+			Play::Single(tetris)
+		}
+		// master [seed]
+		Some("master") => {
+			let seed = args.get(2).and_then(|arg| arg.parse().ok()).unwrap_or_else(rand::random);
+			Play::Mode(Box::new(Master::new(Setup { seed, ..Setup::default() })))
+		}
+		// invisible [seed], fading [seconds before blocks fade] [seed]
+		Some("invisible") => {
+			let seed = args.get(2).and_then(|arg| arg.parse().ok()).unwrap_or_else(rand::random);
+			Play::Mode(Box::new(Hidden::new(None, Setup { seed, ..Setup::default() })))
+		}
+		Some("fading") => {
+			let fade = args.get(2).map_or(modes::hidden::FADE, |arg| seconds(arg));
+			let seed = args.get(3).and_then(|arg| arg.parse().ok()).unwrap_or_else(rand::random);
+			Play::Mode(Box::new(Hidden::new(Some(fade), Setup { seed, ..Setup::default() })))
+		}
+		// screens of the menu are opened at once
+		Some("controls") => return run(Screen::Rebind(Rebind::new())),
+		Some("settings") => return run(Screen::Options(Options::new(Settings::load()))),
+		Some("online") => return run(Screen::Lobby(Lobby::new())),
+		// waits for the connection before the window is opened
+		Some("host") => Play::Online(Online::new(connected(Peer::host(addr)))),
+		Some("join") => Play::Online(Online::new(connected(Peer::join(addr)))),
+		// rollback <player 0 or 1> <local addr> <remote addr> [seed]
+		Some("rollback") => {
+			let player = args.get(2).and_then(|arg| arg.parse().ok()).unwrap_or(0);
+			let local = args.get(3).map(String::as_str).unwrap_or("127.0.0.1:7001");
+			let remote = args.get(4).map(String::as_str).unwrap_or("127.0.0.1:7002");
+			let seed = args.get(5).and_then(|arg| arg.parse().ok()).unwrap_or(0);
+
+			let link = UdpLink::connect(local, remote).unwrap_or_else(|err| {
+				eprintln!("Unable to bind {local}: {err}");
+				std::process::exit(1);
+			});
+			Play::Rollback(Rollback::new(link, player.min(1), seed))
+		}
+		// loopback [latency in frames] [loss] [frames], no window is opened
+		Some("loopback") => {
+			let latency = args.get(2).and_then(|arg| arg.parse().ok()).unwrap_or(6);
+			let loss = args.get(3).and_then(|arg| arg.parse().ok()).unwrap_or(0.1);
+			let frames = args.get(4).and_then(|arg| arg.parse().ok()).unwrap_or(3600);
+
+			println!("{:?}", rollback::loopback(frames, latency, loss, 0));
+			return Ok(());
+		}
+		// theme [name or file], it's printed to start a theme of your own from
+		Some("theme") => {
+			let name = args.get(2).map(String::as_str).unwrap_or_default();
+			let theme = Theme::load(name).unwrap_or_else(|err| {
+				eprintln!("{err}");
+				std::process::exit(1);
+			});
+
+			print!("{}", toml::to_string(&theme).expect("themes are always written"));
+			return Ok(());
+		}
+		None => return run(Screen::Title),
+		// played by the ruleset of the settings
+		_ => match Settings::load().gameplay.setup(rand::random()) {
+			Ok(setup) => Play::Single(Tetris::with(setup)),
+			Err(err) => {
+				eprintln!("{err}");
+				std::process::exit(1);
+			}
+		},
+	};
+
+	run(Screen::Play(play, None))
 
 	// let mut tetris = Tetris::new();
 	// tetris.proceed();
@@ -21,3 +182,50 @@ fn main() -> iced::Result {
 	// 	println!("{:?}", tile);
 	// }
 }
+
+
+// the window is opened by the display settings, borders can't be changed later
+fn run(screen: Screen) -> iced::Result {
+	let display = Settings::load().display;
+
+	Game::run(iced::Settings {
+		window: window::Settings {
+			size: (display.resolution.width, display.resolution.height),
+			min_size: Some((240, 240)),
+			decorations: display.window != Window::Undecorated,
+			..window::Settings::default()
+		},
+		..iced::Settings::with_flags(screen)
+	})
+}
+
+// a positive number of seconds, the game isn't started with a wrong one
+fn seconds(arg: &str) -> std::time::Duration {
+	arg.parse::<f32>()
+		.ok()
+		.filter(|secs| *secs > 0.0)
+		.and_then(|secs| std::time::Duration::try_from_secs_f32(secs).ok())
+		.unwrap_or_else(|| {
+			eprintln!("Invalid number of seconds: {arg}");
+			std::process::exit(1);
+		})
+}
+
+fn minutes(arg: &str) -> std::time::Duration {
+	arg.parse::<u64>()
+		.ok()
+		.filter(|minutes| *minutes > 0)
+		.and_then(|minutes| minutes.checked_mul(60))
+		.map(std::time::Duration::from_secs)
+		.unwrap_or_else(|| {
+			eprintln!("Invalid number of minutes: {arg}");
+			std::process::exit(1);
+		})
+}
+
+fn connected(peer: std::io::Result<Peer>) -> Peer {
+	peer.unwrap_or_else(|err| {
+		eprintln!("Unable to connect: {err}");
+		std::process::exit(1);
+	})
+}
@@ -0,0 +1,83 @@
+pub mod dig;
+pub mod hidden;
+pub mod marathon;
+pub mod master;
+pub mod puzzle;
+pub mod sprint;
+pub mod ultra;
+
+pub use {
+	dig::Dig,
+	hidden::Hidden,
+	marathon::Marathon,
+	master::Master,
+	puzzle::Puzzle,
+	sprint::Sprint,
+	ultra::Ultra,
+};
+
+use {
+	crate::{
+		game::Msg,
+		tetris::Tetris,
+	},
+	std::time::Duration,
+};
+
+
+// single player game with its own goal, it's played on a single board
+pub trait Mode {
+	fn tetris(&self) -> &Tetris;
+
+	// advances the clock of the mode and the game
+	fn tick(&mut self, dt: Duration);
+
+	fn react_to(&mut self, msg: Msg);
+
+	// the key of the action is released, it matters for auto shift
+	fn release(&mut self, _msg: Msg) {}
+
+	// lines of text shown next to the board
+	fn hud(&self) -> Vec<String>;
+
+	fn is_over(&self) -> bool;
+
+	// shown under the board once it's over
+	fn status(&self) -> Option<String>;
+
+	// how locked blocks are shown
+	fn visibility(&self) -> Visibility {
+		Visibility::Visible
+	}
+
+	// whether the outline of the stack is shown right now
+	fn outline(&self) -> bool {
+		false
+	}
+}
+
+
+// time it takes a fading block to disappear
+pub const FADE_OUT: Duration = Duration::from_millis(500);
+
+
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub enum Visibility {
+	Visible,
+	// blocks start fading once they have been locked for the given time
+	Fading(Duration),
+	Invisible,
+}
+
+impl Visibility {
+	// opacity of the block locked the given time ago
+	pub fn alpha(&self, age: Duration) -> f32 {
+		match *self {
+			Visibility::Visible => 1.0,
+			Visibility::Fading(after) if age <= after => 1.0,
+			Visibility::Fading(after) =>
+				1.0 - ((age - after).as_secs_f32() / FADE_OUT.as_secs_f32()).min(1.0),
+			Visibility::Invisible => 0.0,
+		}
+	}
+}
@@ -0,0 +1,160 @@
+use {
+	crate::{
+		game::Msg,
+		records::format_time,
+		tetris::{
+			Setup,
+			Tetris,
+		},
+	},
+	super::Mode,
+	rand::{
+		rngs::StdRng,
+		Rng,
+		SeedableRng,
+	},
+	std::time::Duration,
+};
+
+
+pub const ROWS: usize = 8;
+// how often a new row rises in the survival
+pub const INTERVAL: Duration = Duration::from_secs(4);
+
+
+// digging through messy garbage as fast as possible,
+// in the survival new rows keep rising until the game is over
+pub struct Dig {
+	tetris: Tetris,
+	// it's the survival if rows rise
+	interval: Option<Duration>,
+	time: Duration,
+	// time since the last row has risen
+	since: Duration,
+	// number of garbage rows that have been on the map
+	rows: usize,
+	// picks holes in garbage
+	rng: StdRng,
+	hole: usize,
+	finished: bool,
+}
+
+impl Dig {
+	pub fn new(rows: usize, interval: Option<Duration>, setup: Setup) -> Dig {
+		let seed = setup.seed;
+		let rows = rows.clamp(1, setup.height / 2);
+
+		let mut dig = Dig {
+			tetris: Tetris::with(setup),
+			interval,
+			time: Duration::ZERO,
+			since: Duration::ZERO,
+			rows: 0,
+			rng: StdRng::seed_from_u64(seed),
+			hole: 0,
+			finished: false,
+		};
+
+		for _ in 0..rows {
+			dig.rise();
+		}
+		dig.tetris.spawn();
+
+		dig
+	}
+
+	// number of garbage rows that have been burned
+	pub fn dug(&self) -> usize {
+		self.rows - self.tetris.garbage_rows()
+	}
+
+	// pushes up a single row, its hole is never under the previous one
+	fn rise(&mut self) {
+		let width = self.tetris.width();
+		let mut hole = self.rng.gen_range(0..width);
+		while self.rows > 0 && hole == self.hole {
+			hole = self.rng.gen_range(0..width);
+		}
+
+		self.hole = hole;
+		self.rows += 1;
+		self.tetris.add_garbage(1, &[hole]);
+	}
+
+	fn check(&mut self) {
+		self.finished = match self.interval {
+			Some(_) => self.tetris.is_over(),
+			None => self.tetris.is_over() || self.tetris.garbage_rows() == 0,
+		};
+
+		// the next tetrimino comes at once
+		if !self.finished {
+			self.tetris.spawn();
+		}
+	}
+}
+
+impl Mode for Dig {
+	fn tetris(&self) -> &Tetris {
+		&self.tetris
+	}
+
+	fn tick(&mut self, dt: Duration) {
+		if self.is_over() {
+			return;
+		}
+
+		self.time += dt;
+		self.tetris.tick(dt);
+
+		if let Some(interval) = self.interval {
+			self.since += dt;
+			while self.since >= interval && !self.tetris.is_over() {
+				self.since -= interval;
+				self.rise();
+			}
+		}
+
+		self.check();
+	}
+
+	fn react_to(&mut self, msg: Msg) {
+		if self.is_over() {
+			return;
+		}
+
+		self.tetris.react_to(msg);
+		self.check();
+	}
+
+	fn hud(&self) -> Vec<String> {
+		let title = match self.interval {
+			Some(_) => String::from("Dig survival"),
+			None => format!("Dig {}", self.rows),
+		};
+
+		vec!(
+			title,
+			format_time(self.time),
+			format!("dug {}", self.dug()),
+			format!("garbage left {}", self.tetris.garbage_rows()),
+			format!("pieces {}", self.tetris.pieces()),
+		)
+	}
+
+	fn is_over(&self) -> bool {
+		self.finished
+	}
+
+	fn status(&self) -> Option<String> {
+		if !self.finished {
+			None
+		} else if self.interval.is_some() {
+			Some(format!("Survived {}, dug {} rows", format_time(self.time), self.dug()))
+		} else if self.tetris.is_over() {
+			Some(String::from("Game over"))
+		} else {
+			Some(format!("Dug out in {}", format_time(self.time)))
+		}
+	}
+}
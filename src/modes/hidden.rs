@@ -0,0 +1,124 @@
+use {
+	crate::{
+		game::Msg,
+		records::format_time,
+		tetris::{
+			Setup,
+			Tetris,
+		},
+	},
+	super::{
+		Mode,
+		Visibility,
+	},
+	std::time::Duration,
+};
+
+
+// time blocks are shown before fading by default
+pub const FADE: Duration = Duration::from_secs(3);
+// how long the outline of the stack flashes after a clear
+pub const FLASH: Duration = Duration::from_millis(300);
+
+
+// playing until the game is over with the stack out of sight,
+// it fades out some time after the lock or vanishes at once,
+// only the alive tetrimino is shown and the outline flashes on clears
+pub struct Hidden {
+	tetris: Tetris,
+	// it's invisible at once if there is no time
+	fade: Option<Duration>,
+	time: Duration,
+	// time left for the outline to be shown
+	flash: Duration,
+}
+
+impl Hidden {
+	pub fn new(fade: Option<Duration>, setup: Setup) -> Hidden {
+		let mut tetris = Tetris::with(setup);
+		tetris.spawn();
+
+		Hidden {
+			tetris,
+			fade,
+			time: Duration::ZERO,
+			flash: Duration::ZERO,
+		}
+	}
+
+	fn check(&mut self, lines: usize) {
+		if self.tetris.lines() > lines {
+			self.flash = FLASH;
+		}
+
+		// the next tetrimino comes at once
+		self.tetris.spawn();
+	}
+}
+
+impl Mode for Hidden {
+	fn tetris(&self) -> &Tetris {
+		&self.tetris
+	}
+
+	fn tick(&mut self, dt: Duration) {
+		if self.is_over() {
+			return;
+		}
+
+		let lines = self.tetris.lines();
+
+		self.time += dt;
+		self.flash = self.flash.saturating_sub(dt);
+		self.tetris.tick(dt);
+		self.check(lines);
+	}
+
+	fn react_to(&mut self, msg: Msg) {
+		if self.is_over() {
+			return;
+		}
+
+		let lines = self.tetris.lines();
+
+		self.tetris.react_to(msg);
+		self.check(lines);
+	}
+
+	fn hud(&self) -> Vec<String> {
+		let title = match self.fade {
+			Some(fade) => format!("Fading {:.1}s", fade.as_secs_f32()),
+			None => String::from("Invisible"),
+		};
+
+		vec!(
+			title,
+			format_time(self.time),
+			format!("level {}", self.tetris.level() + 1),
+			format!("lines {}", self.tetris.lines()),
+			format!("score {}", self.tetris.score()),
+		)
+	}
+
+	fn is_over(&self) -> bool {
+		self.tetris.is_over()
+	}
+
+	fn status(&self) -> Option<String> {
+		self.is_over().then(|| format!("Game over, {} lines in {}",
+			self.tetris.lines(), format_time(self.time)))
+	}
+
+	// the whole stack is revealed once it's over
+	fn visibility(&self) -> Visibility {
+		match self.fade {
+			_ if self.is_over() => Visibility::Visible,
+			Some(fade) => Visibility::Fading(fade),
+			None => Visibility::Invisible,
+		}
+	}
+
+	fn outline(&self) -> bool {
+		!self.flash.is_zero() && !self.is_over()
+	}
+}
@@ -0,0 +1,122 @@
+use {
+	crate::{
+		game::Msg,
+		records::format_time,
+		tetris::{
+			Setup,
+			Tetris,
+		},
+	},
+	super::Mode,
+	std::time::Duration,
+};
+
+
+pub const GOAL: usize = 150;
+
+
+// playing through the levels until the goal of lines is reached,
+// the endless one goes on at the top speed until the game is over
+pub struct Marathon {
+	tetris: Tetris,
+	// it's endless if there is no goal
+	goal: Option<usize>,
+	time: Duration,
+	won: bool,
+}
+
+impl Marathon {
+	pub fn new(goal: Option<usize>, setup: Setup) -> Marathon {
+		let mut tetris = Tetris::with(setup);
+		tetris.spawn();
+
+		Marathon {
+			tetris,
+			goal: goal.map(|goal| goal.max(1)),
+			time: Duration::ZERO,
+			won: false,
+		}
+	}
+
+	pub fn won(&self) -> bool {
+		self.won
+	}
+
+	fn check(&mut self) {
+		if let Some(goal) = self.goal {
+			self.won = self.tetris.lines() >= goal;
+		}
+
+		// the next tetrimino comes at once
+		if !self.won {
+			self.tetris.spawn();
+		}
+	}
+}
+
+impl Mode for Marathon {
+	fn tetris(&self) -> &Tetris {
+		&self.tetris
+	}
+
+	fn tick(&mut self, dt: Duration) {
+		if self.is_over() {
+			return;
+		}
+
+		self.time += dt;
+		self.tetris.tick(dt);
+		self.check();
+	}
+
+	fn react_to(&mut self, msg: Msg) {
+		if self.is_over() {
+			return;
+		}
+
+		self.tetris.react_to(msg);
+		self.check();
+	}
+
+	fn hud(&self) -> Vec<String> {
+		let (title, lines) = match self.goal {
+			Some(goal) => (format!("Marathon {goal}"), format!("lines {}/{goal}", self.tetris.lines())),
+			None => (String::from("Endless"), format!("lines {}", self.tetris.lines())),
+		};
+
+		let mut hud = vec!(
+			title,
+			format_time(self.time),
+			format!("level {}", self.tetris.level() + 1),
+			lines,
+			format!("score {}", self.tetris.score()),
+		);
+
+		// the results
+		if self.is_over() {
+			let pieces = self.tetris.pieces();
+			let seconds = self.time.as_secs_f64().max(1.0);
+
+			hud.push(String::new());
+			hud.push(format!("pieces {pieces}"));
+			hud.push(format!("{:.2} pieces/s", pieces as f64 / seconds));
+			hud.push(format!("{:.1} lines/min", self.tetris.lines() as f64 * 60.0 / seconds));
+		}
+
+		hud
+	}
+
+	fn is_over(&self) -> bool {
+		self.won || self.tetris.is_over()
+	}
+
+	fn status(&self) -> Option<String> {
+		if self.won {
+			Some(format!("Victory in {}, score {}", format_time(self.time), self.tetris.score()))
+		} else if self.tetris.is_over() {
+			Some(format!("Game over, score {}", self.tetris.score()))
+		} else {
+			None
+		}
+	}
+}
@@ -0,0 +1,262 @@
+use {
+	crate::{
+		game::Msg,
+		records::format_time,
+		tetris::{
+			Setup,
+			Tetris,
+		},
+	},
+	super::Mode,
+	std::time::Duration,
+};
+
+
+// the last level, the game is won once it's reached
+pub const GOAL: usize = 999;
+// levels in a section, timings change from section to section
+const SECTION: usize = 100;
+// interval of the auto shift once it's charged, a single frame
+const ARR: Duration = Duration::from_millis(16);
+
+// timings of each section in millis
+const TIMINGS: [Timing; 10] = [
+	Timing::new(417, 667, 233, 500),
+	Timing::new(417, 667, 233, 500),
+	Timing::new(417, 667, 233, 500),
+	Timing::new(417, 500, 233, 500),
+	Timing::new(417, 417, 200, 500),
+	Timing::new(417, 417, 133, 500),
+	Timing::new(417, 267, 133, 500),
+	Timing::new(267, 200, 133, 500),
+	Timing::new(200, 100, 133, 500),
+	Timing::new(200, 100, 100, 283),
+];
+
+
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub struct Timing {
+	// entry delay, time between the lock and the next spawn
+	pub are: Duration,
+	// added to the entry delay when lines are burned
+	pub clear: Duration,
+	// delayed auto shift, time a shift key is held before it repeats
+	pub das: Duration,
+	// time the tetrimino may rest on the stack before it's locked
+	pub lock: Duration,
+}
+
+impl Timing {
+	const fn new(are: u64, clear: u64, das: u64, lock: u64) -> Timing {
+		Timing {
+			are: Duration::from_millis(are),
+			clear: Duration::from_millis(clear),
+			das: Duration::from_millis(das),
+			lock: Duration::from_millis(lock),
+		}
+	}
+}
+
+
+// tetriminos appear right at the bottom (20G) and the delays shrink
+// section by section, the level grows by each piece and each burned line
+// but a piece alone doesn't finish a section
+pub struct Master {
+	tetris: Tetris,
+	level: usize,
+	time: Duration,
+	// time left until the next tetrimino spawns
+	entry: Duration,
+	// time the alive tetrimino has rested on the stack
+	rest: Duration,
+	// the shift key being held and the time it has been held for
+	shift: Option<(Msg, Duration)>,
+	won: bool,
+}
+
+impl Master {
+	pub fn new(setup: Setup) -> Master {
+		let mut master = Master {
+			tetris: Tetris::with(setup),
+			level: 0,
+			time: Duration::ZERO,
+			entry: Duration::ZERO,
+			rest: Duration::ZERO,
+			shift: None,
+			won: false,
+		};
+		master.enter();
+
+		master
+	}
+
+	pub fn timing(&self) -> Timing {
+		TIMINGS[(self.level / SECTION).min(TIMINGS.len() - 1)]
+	}
+
+	pub fn won(&self) -> bool {
+		self.won
+	}
+
+	// the next tetrimino spawns and falls to the bottom at once
+	fn enter(&mut self) {
+		self.tetris.spawn();
+		self.tetris.fall();
+		self.rest = Duration::ZERO;
+
+		if !self.tetris.is_over() && self.level % SECTION != SECTION - 1 && self.level < GOAL - 1 {
+			self.level += 1;
+		}
+	}
+
+	// the tetrimino falls after every move, resting starts over once it's lower
+	fn settle(&mut self) {
+		if self.tetris.fall() > 0 {
+			self.rest = Duration::ZERO;
+		}
+	}
+
+	// counts the lines of the lock if there was one and starts the entry delay
+	fn check(&mut self, pieces: usize, lines: usize) {
+		if self.tetris.pieces() == pieces || self.tetris.is_over() {
+			return;
+		}
+
+		let burned = self.tetris.lines() - lines;
+		let timing = self.timing();
+
+		self.level = (self.level + burned).min(GOAL);
+		self.won = burned > 0 && self.level >= GOAL;
+		self.entry = match burned {
+			0 => timing.are,
+			_ => timing.are + timing.clear,
+		};
+	}
+
+	fn act(&mut self, msg: Msg) {
+		let (pieces, lines) = (self.tetris.pieces(), self.tetris.lines());
+		self.tetris.react_to(msg);
+		self.settle();
+		self.check(pieces, lines);
+	}
+}
+
+impl Mode for Master {
+	fn tetris(&self) -> &Tetris {
+		&self.tetris
+	}
+
+	fn tick(&mut self, dt: Duration) {
+		if self.is_over() {
+			return;
+		}
+
+		self.time += dt;
+		self.tetris.advance(dt);
+		let timing = self.timing();
+
+		// the auto shift is charged even while the next tetrimino is awaited
+		if let Some((msg, held)) = self.shift.clone() {
+			let repeats = |held: Duration| match held.checked_sub(timing.das) {
+				Some(charged) => charged.as_millis() / ARR.as_millis() + 1,
+				None => 0,
+			};
+			let steps = repeats(held + dt) - repeats(held);
+
+			self.shift = Some((msg.clone(), held + dt));
+			for _ in 0..steps {
+				self.act(msg.clone());
+			}
+		}
+
+		if self.tetris.is_alive() {
+			self.rest += dt;
+			if self.rest >= timing.lock {
+				let (pieces, lines) = (self.tetris.pieces(), self.tetris.lines());
+				self.tetris.proceed();
+				self.check(pieces, lines);
+			}
+		} else if !self.won {
+			self.entry = self.entry.saturating_sub(dt);
+			if self.entry.is_zero() {
+				self.enter();
+			}
+		}
+	}
+
+	fn react_to(&mut self, msg: Msg) {
+		if self.is_over() {
+			return;
+		}
+
+		// repeated presses of the held key come from the keyboard itself
+		if matches!(msg, Msg::ShiftLft | Msg::ShiftRgt) {
+			if matches!(&self.shift, Some((held, _)) if *held == msg) {
+				return;
+			}
+			self.shift = Some((msg.clone(), Duration::ZERO));
+		}
+
+		self.act(msg);
+	}
+
+	fn release(&mut self, msg: Msg) {
+		if matches!(&self.shift, Some((held, _)) if *held == msg) {
+			self.shift = None;
+		}
+	}
+
+	fn hud(&self) -> Vec<String> {
+		let timing = self.timing();
+		let section = (self.level / SECTION + 1) * SECTION;
+
+		vec!(
+			String::from("Master"),
+			format_time(self.time),
+			format!("level {}/{}", self.level, section.min(GOAL)),
+			format!("lines {}", self.tetris.lines()),
+			format!("score {}", self.tetris.score()),
+			String::new(),
+			format!("are {}ms", timing.are.as_millis()),
+			format!("clear {}ms", timing.clear.as_millis()),
+			format!("das {}ms", timing.das.as_millis()),
+			format!("lock {}ms", timing.lock.as_millis()),
+		)
+	}
+
+	fn is_over(&self) -> bool {
+		self.won || self.tetris.is_over()
+	}
+
+	fn status(&self) -> Option<String> {
+		if self.won {
+			Some(format!("Mastered in {}", format_time(self.time)))
+		} else if self.tetris.is_over() {
+			Some(format!("Game over at level {}", self.level))
+		} else {
+			None
+		}
+	}
+}
+
+
+#[cfg(test)]
+mod tests {
+	use super::*;
+
+	#[test]
+	fn pieces_stop_before_the_goal() {
+		let mut master = Master::new(Setup::default());
+		master.level = GOAL - 2;
+		master.enter();
+		master.enter();
+		assert_eq!(master.level, GOAL - 1);
+
+		// a lock without lines doesn't win
+		let (pieces, lines) = (master.tetris.pieces(), master.tetris.lines());
+		master.tetris.hard_drop();
+		master.check(pieces, lines);
+		assert_eq!(master.level, GOAL - 1);
+		assert!(!master.won());
+	}
+}
@@ -0,0 +1,384 @@
+// preset board with a fixed queue of tetriminos and a goal, for example
+//
+//	name = "Tetris"
+//	goal = "lines 4"			# or "perfect clear", "t-spin double"
+//	queue = ["O", "I"]
+//	board = '''
+//	XXXX......
+//	XXXX.XXXXX
+//	XXXX.XXXXX
+//	XXXX.XXXXX
+//	XXXX.XXXXX
+//	'''
+//
+// the board is written from the top, dots are empty, G is garbage,
+// letters are blocks of pieces and other characters are gray blocks,
+// the board may be given as a fumen instead, its pieces are the queue
+// unless the queue is given
+//
+//	fumen = "v115@9gF8DeF8DeF8DeF8NeAgH"
+// it's solved if the goal is reached once the queue runs out
+
+use {
+	crate::{
+		game::Msg,
+		tetris::{
+			tetrominoes,
+			Fumen,
+			FumenError,
+			Map,
+			Randomizer,
+			Setup,
+			Tetris,
+		},
+	},
+	super::Mode,
+	serde::Deserialize,
+	std::{
+		fmt,
+		fs,
+		io,
+		path::Path,
+		str::FromStr,
+		time::Duration,
+	},
+};
+
+
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub enum Goal {
+	Lines(usize),
+	PerfectClear,
+	TSpinDouble,
+}
+
+impl FromStr for Goal {
+	type Err = PuzzleError;
+
+	fn from_str(string: &str) -> Result<Goal, PuzzleError> {
+		let words: Vec<&str> = string.split_whitespace().collect();
+
+		match words.as_slice() {
+			["lines", lines] => lines
+				.parse()
+				.map(Goal::Lines)
+				.map_err(|_| PuzzleError::Goal(string.to_string())),
+			["perfect", "clear"] => Ok(Goal::PerfectClear),
+			["t-spin", "double"] => Ok(Goal::TSpinDouble),
+			_ => Err(PuzzleError::Goal(string.to_string())),
+		}
+	}
+}
+
+impl fmt::Display for Goal {
+	fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+		match self {
+			Goal::Lines(1) => write!(f, "clear a line"),
+			Goal::Lines(lines) => write!(f, "clear {lines} lines"),
+			Goal::PerfectClear => write!(f, "perfect clear"),
+			Goal::TSpinDouble => write!(f, "t-spin double"),
+		}
+	}
+}
+
+
+#[derive(Clone)]
+pub struct Puzzle {
+	name: String,
+	tetris: Tetris,
+	goal: Goal,
+	// number of tetriminos in the queue
+	queue: usize,
+	// whether the goal has been reached
+	reached: bool,
+	finished: bool,
+}
+
+impl Puzzle {
+	pub fn load(path: &Path) -> Result<Puzzle, PuzzleError> {
+		fs::read_to_string(path)
+			.map_err(PuzzleError::Io)?
+			.parse()
+	}
+
+	pub fn solved(&self) -> bool {
+		self.finished && self.reached
+	}
+
+	fn check(&mut self) {
+		match (self.goal, self.tetris.last_lock()) {
+			(Goal::Lines(lines), _) => self.reached = self.tetris.lines() >= lines,
+			// later locks may leave blocks on the map again
+			(Goal::PerfectClear, Some(lock)) => self.reached = lock.perfect,
+			(Goal::TSpinDouble, Some(lock)) => self.reached |= lock.tspin && lock.lines == 2,
+			_ => (),
+		}
+
+		// the next tetrimino comes at once
+		self.tetris.spawn();
+
+		self.finished = self.tetris.is_over() || self.tetris.exhausted();
+		self.reached &= !self.tetris.is_over();
+	}
+}
+
+impl FromStr for Puzzle {
+	type Err = PuzzleError;
+
+	fn from_str(string: &str) -> Result<Puzzle, PuzzleError> {
+		let file: File = toml::from_str(string)
+			.map_err(|err| PuzzleError::Syntax(err.message().to_string()))?;
+
+		let fumen: Option<Fumen> = file.fumen
+			.as_deref()
+			.map(str::parse)
+			.transpose()
+			.map_err(|err: FumenError| PuzzleError::Board(err.to_string()))?;
+
+		let names = match &fumen {
+			Some(fumen) if file.queue.is_empty() =>
+				fumen.queue().iter().map(char::to_string).collect(),
+			_ => file.queue,
+		};
+
+		let pieces = tetrominoes();
+		let queue = names
+			.iter()
+			.map(|name| pieces
+				.iter()
+				.position(|piece| &piece.name == name)
+				.map(|id| id as u8)
+				.ok_or_else(|| PuzzleError::UnknownPiece(name.clone())))
+			.collect::<Result<Vec<u8>, _>>()?;
+
+		if queue.is_empty() {
+			return Err(PuzzleError::EmptyQueue);
+		}
+
+		let mut tetris = Tetris::with(Setup {
+			randomizer: Randomizer::Fixed(queue.clone()),
+			..Setup::default()
+		});
+
+		// there may be no board at all
+		let board = match (&fumen, file.board.trim().is_empty()) {
+			(Some(_), false) => return Err(PuzzleError::Board(String::from("both board and fumen are given"))),
+			(Some(fumen), true) => fumen.pages.first().map(|page| Ok(page.board())),
+			(None, false) => Some(file.board.parse::<Map>()),
+			(None, true) => None,
+		};
+		if let Some(board) = board {
+			board
+				.and_then(|board| tetris.load_map(&board))
+				.map_err(|err| PuzzleError::Board(err.to_string()))?;
+		}
+
+		let mut puzzle = Puzzle {
+			name: file.name,
+			tetris,
+			goal: file.goal.parse()?,
+			queue: queue.len(),
+			reached: false,
+			finished: false,
+		};
+		puzzle.check();
+
+		Ok(puzzle)
+	}
+}
+
+impl Mode for Puzzle {
+	fn tetris(&self) -> &Tetris {
+		&self.tetris
+	}
+
+	// there is no gravity, the player takes time to think
+	fn tick(&mut self, _dt: Duration) {}
+
+	fn react_to(&mut self, msg: Msg) {
+		if self.is_over() {
+			return;
+		}
+
+		self.tetris.react_to(msg);
+		self.check();
+	}
+
+	fn hud(&self) -> Vec<String> {
+		vec!(
+			self.name.clone(),
+			format!("goal: {}", self.goal),
+			format!("pieces {}/{}", self.tetris.pieces(), self.queue),
+			format!("lines {}", self.tetris.lines()),
+		)
+	}
+
+	fn is_over(&self) -> bool {
+		self.finished
+	}
+
+	fn status(&self) -> Option<String> {
+		match (self.finished, self.reached) {
+			(true, true) => Some(String::from("Solved")),
+			(true, false) => Some(String::from("Failed")),
+			_ => None,
+		}
+	}
+}
+
+
+#[derive(Debug)]
+pub enum PuzzleError {
+	Io(io::Error),
+	Syntax(String),
+	Goal(String),
+	UnknownPiece(String),
+	EmptyQueue,
+	Board(String),
+}
+
+impl fmt::Display for PuzzleError {
+	fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+		match self {
+			PuzzleError::Io(err) => write!(f, "unable to read the puzzle: {err}"),
+			PuzzleError::Syntax(msg) => write!(f, "invalid puzzle: {msg}"),
+			PuzzleError::Goal(goal) =>
+				write!(f, "unknown goal {goal}, it's lines N, perfect clear or t-spin double"),
+			PuzzleError::UnknownPiece(name) => write!(f, "unknown piece {name}"),
+			PuzzleError::EmptyQueue => write!(f, "the queue is empty"),
+			PuzzleError::Board(msg) => write!(f, "invalid board: {msg}"),
+		}
+	}
+}
+
+impl std::error::Error for PuzzleError {}
+
+
+// the file as it's written
+#[derive(Deserialize)]
+#[serde(deny_unknown_fields)]
+struct File {
+	#[serde(default)]
+	name: String,
+	goal: String,
+	#[serde(default)]
+	queue: Vec<String>,
+	#[serde(default)]
+	board: String,
+	fumen: Option<String>,
+}
+
+
+#[cfg(test)]
+mod tests {
+	use super::*;
+
+	fn parse(puzzle: &str) -> Puzzle {
+		puzzle.parse().unwrap()
+	}
+
+	// numbers of finished games and solved ones over every way to put the queue
+	fn outcomes(puzzle: &Puzzle) -> (usize, usize) {
+		if puzzle.is_over() {
+			return (1, puzzle.solved() as usize);
+		}
+
+		puzzle.tetris.placements().iter().fold((0, 0), |(played, solved), placement| {
+			let mut next = puzzle.clone();
+			for msg in placement.moves.iter().chain([&Msg::Proceed]) {
+				next.react_to(msg.clone());
+			}
+			let (more, won) = outcomes(&next);
+			(played + more, solved + won)
+		})
+	}
+
+	#[test]
+	fn goals() {
+		assert_eq!("lines 4".parse::<Goal>().unwrap(), Goal::Lines(4));
+		assert_eq!(" perfect  clear ".parse::<Goal>().unwrap(), Goal::PerfectClear);
+		assert_eq!("t-spin double".parse::<Goal>().unwrap(), Goal::TSpinDouble);
+
+		for goal in ["", "lines", "lines four", "lines -1", "perfect", "t-spin triple"] {
+			assert!(matches!(goal.parse::<Goal>(), Err(PuzzleError::Goal(_))), "{goal}");
+		}
+	}
+
+	#[test]
+	fn errors() {
+		let parse = |puzzle: &str| puzzle.parse::<Puzzle>().err();
+		assert!(matches!(parse("queue = [\"I\"]"), Some(PuzzleError::Syntax(_))));
+		assert!(matches!(parse("goal = \"lines 1\"\nqueue = [\"plus\"]"), Some(PuzzleError::UnknownPiece(_))));
+		assert!(matches!(parse("goal = \"lines 1\""), Some(PuzzleError::EmptyQueue)));
+		assert!(matches!(parse("goal = \"lines 1\"\nqueue = [\"I\"]\nboard = \"..\\n.\""), Some(PuzzleError::Board(_))));
+		assert!(matches!(
+			parse("goal = \"lines 1\"\nfumen = \"v115@vhBRQJAAA\"\nboard = \"..........\""),
+			Some(PuzzleError::Board(_))));
+	}
+
+	// each shipped puzzle can be solved but not by any placement
+	#[test]
+	fn shipped() {
+		for entry in fs::read_dir("puzzles").unwrap() {
+			let path = entry.unwrap().path();
+			let puzzle = Puzzle::load(&path).unwrap();
+			let (played, solved) = outcomes(&puzzle);
+			assert!(solved > 0 && solved < played, "{}", path.display());
+		}
+	}
+
+	#[test]
+	fn lines() {
+		let puzzle = parse("
+			goal = \"lines 2\"
+			queue = [\"O\"]
+			board = '''
+			XXXXXXXX..
+			XXXXXXXX..
+			'''
+		");
+		assert_eq!(outcomes(&puzzle).1, 1);
+	}
+
+	// a later lock that leaves blocks undoes the perfect clear
+	#[test]
+	fn perfect_clear() {
+		let board = "
+			board = '''
+			XXXXXXXX..
+			XXXXXXXX..
+			'''
+		";
+		let once = parse(&format!("goal = \"perfect clear\"\nqueue = [\"O\"]\n{board}"));
+		assert_eq!(outcomes(&once).1, 1);
+
+		let twice = parse(&format!("goal = \"perfect clear\"\nqueue = [\"O\", \"O\"]\n{board}"));
+		assert_eq!(outcomes(&twice).1, 0);
+	}
+
+	// the same double without a spin doesn't count
+	#[test]
+	fn t_spin_double() {
+		let puzzle = parse("
+			goal = \"t-spin double\"
+			queue = [\"T\"]
+			board = '''
+			XXXXXXX...
+			XXXXXXXX.X
+			'''
+		");
+		let (played, solved) = outcomes(&puzzle);
+		assert!(played > 0);
+		assert_eq!(solved, 0);
+
+		let lines = parse("
+			goal = \"lines 2\"
+			queue = [\"T\"]
+			board = '''
+			XXXXXXX...
+			XXXXXXXX.X
+			'''
+		");
+		assert!(outcomes(&lines).1 > 0);
+	}
+}
@@ -0,0 +1,163 @@
+use {
+	crate::{
+		game::Msg,
+		records::{
+			format_delta,
+			format_time,
+			Records,
+			SprintRecord,
+		},
+		tetris::{
+			Setup,
+			Tetris,
+		},
+	},
+	super::Mode,
+	std::time::Duration,
+};
+
+
+pub const TARGET: usize = 40;
+// split time is taken each time that many lines are burned
+pub const SPLIT: usize = 10;
+
+
+// burning the target of lines as fast as possible
+pub struct Sprint {
+	tetris: Tetris,
+	target: usize,
+	time: Duration,
+	splits: Vec<Duration>,
+	// the personal best for the same target
+	best: Option<SprintRecord>,
+	finished: bool,
+	// whether the finished run beats the best one
+	new_best: bool,
+}
+
+impl Sprint {
+	pub fn new(target: usize, setup: Setup) -> Sprint {
+		let mut tetris = Tetris::with(setup);
+		tetris.spawn();
+		let target = target.max(1);
+
+		Sprint {
+			tetris,
+			target,
+			time: Duration::ZERO,
+			splits: vec!(),
+			best: Records::load().sprint.get(&target.to_string()).cloned(),
+			finished: false,
+			new_best: false,
+		}
+	}
+
+	pub fn time(&self) -> Duration {
+		self.time
+	}
+
+	pub fn splits(&self) -> &[Duration] {
+		&self.splits
+	}
+
+	// takes splits and finishes the run once the target is reached
+	fn check(&mut self) {
+		let lines = self.tetris.lines().min(self.target);
+
+		while self.splits.len() < lines / SPLIT {
+			self.splits.push(self.time);
+		}
+
+		if lines >= self.target {
+			self.finished = true;
+			self.new_best = self.best.as_ref().is_none_or(|best| self.time < best.time());
+
+			if self.new_best {
+				let record = SprintRecord {
+					time: self.time.as_millis() as u64,
+					splits: self.splits.iter().map(|split| split.as_millis() as u64).collect(),
+				};
+
+				// other records may have changed since the start
+				let saved = Records::read().and_then(|mut records| {
+					records.sprint.insert(self.target.to_string(), record);
+					records.save().map_err(|err| err.to_string())
+				});
+				if let Err(err) = saved {
+					eprintln!("Unable to save records: {err}");
+				}
+			}
+		}
+
+		// the next tetrimino comes at once
+		self.tetris.spawn();
+	}
+}
+
+impl Mode for Sprint {
+	fn tetris(&self) -> &Tetris {
+		&self.tetris
+	}
+
+	fn tick(&mut self, dt: Duration) {
+		if self.is_over() {
+			return;
+		}
+
+		self.time += dt;
+		self.tetris.tick(dt);
+		self.check();
+	}
+
+	fn react_to(&mut self, msg: Msg) {
+		if self.is_over() {
+			return;
+		}
+
+		self.tetris.react_to(msg);
+		self.check();
+	}
+
+	fn hud(&self) -> Vec<String> {
+		let mut hud = vec!(
+			format!("Sprint {}", self.target),
+			format_time(self.time),
+			format!("lines {}/{}", self.tetris.lines().min(self.target), self.target),
+		);
+
+		// splits are compared with the same ones of the best run
+		for (i, split) in self.splits.iter().enumerate() {
+			let delta = self.best
+				.as_ref()
+				.and_then(|best| best.split(i))
+				.map_or(String::new(), |best| format_delta(*split, best));
+			hud.push(format!("{:>3} {} {delta}", (i + 1) * SPLIT, format_time(*split)));
+		}
+
+		if let Some(best) = &self.best {
+			hud.push(format!("best {}", format_time(best.time())));
+		}
+
+		hud
+	}
+
+	fn is_over(&self) -> bool {
+		self.finished || self.tetris.is_over()
+	}
+
+	fn status(&self) -> Option<String> {
+		if self.finished {
+			let best = match (&self.best, self.new_best) {
+				(Some(best), true) =>
+					format!(", new best by {:.3}s", (best.time() - self.time).as_secs_f64()),
+				(None, _) => String::from(", new best"),
+				_ => String::new(),
+			};
+			Some(format!("Finished in {}{best}", format_time(self.time)))
+		} else if self.tetris.is_over() {
+			Some(String::from("Game over"))
+		} else {
+			None
+		}
+	}
+}
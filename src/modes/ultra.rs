@@ -0,0 +1,180 @@
+use {
+	crate::{
+		game::Msg,
+		records::{
+			format_time,
+			Records,
+			UltraRecord,
+		},
+		tetris::{
+			Setup,
+			Tetris,
+		},
+	},
+	super::Mode,
+	std::time::Duration,
+};
+
+
+pub const LIMIT: Duration = Duration::from_secs(120);
+
+// names of clears by the number of burned lines
+const CLEARS: [&str; 5] = ["singles", "doubles", "triples", "tetrises", "pentrises"];
+
+
+// scoring as much as possible in the limited time
+pub struct Ultra {
+	tetris: Tetris,
+	limit: Duration,
+	time: Duration,
+	// number of clears and the score earned by them, by burned lines
+	clears: Vec<(usize, usize)>,
+	// the best runs of the same duration, this one is among them if it got there
+	leaderboard: Vec<UltraRecord>,
+	place: Option<usize>,
+	finished: bool,
+}
+
+impl Ultra {
+	pub fn new(limit: Duration, setup: Setup) -> Ultra {
+		let mut tetris = Tetris::with(setup);
+		tetris.spawn();
+
+		Ultra {
+			tetris,
+			limit,
+			time: Duration::ZERO,
+			clears: vec!(),
+			leaderboard: Records::load()
+				.ultra
+				.remove(&limit.as_secs().to_string())
+				.unwrap_or_default(),
+			place: None,
+			finished: false,
+		}
+	}
+
+	pub fn remaining(&self) -> Duration {
+		self.limit.saturating_sub(self.time)
+	}
+
+	// counts the clear made by the last action
+	fn check(&mut self, lines: usize, score: usize) {
+		let burned = self.tetris.lines() - lines;
+		if burned > 0 {
+			if self.clears.len() < burned {
+				self.clears.resize(burned, (0, 0));
+			}
+			self.clears[burned - 1].0 += 1;
+			self.clears[burned - 1].1 += self.tetris.score() - score;
+		}
+
+		if self.time >= self.limit || self.tetris.is_over() {
+			self.finish();
+		}
+
+		// the next tetrimino comes at once
+		self.tetris.spawn();
+	}
+
+	fn finish(&mut self) {
+		self.finished = true;
+
+		let record = UltraRecord {
+			score: self.tetris.score(),
+			lines: self.tetris.lines(),
+		};
+
+		// other records may have changed since the start, a broken file is left alone
+		let mut records = match Records::read() {
+			Ok(records) => records,
+			Err(err) => {
+				eprintln!("Unable to save records: {err}");
+				return;
+			}
+		};
+		self.place = records.add_ultra(self.limit, record);
+		self.leaderboard = records.ultra[&self.limit.as_secs().to_string()].clone();
+
+		if self.place.is_some() {
+			if let Err(err) = records.save() {
+				eprintln!("Unable to save records: {err}");
+			}
+		}
+	}
+}
+
+impl Mode for Ultra {
+	fn tetris(&self) -> &Tetris {
+		&self.tetris
+	}
+
+	// the game isn't played past the limit
+	fn tick(&mut self, dt: Duration) {
+		if self.is_over() {
+			return;
+		}
+
+		let dt = dt.min(self.remaining());
+		let (lines, score) = (self.tetris.lines(), self.tetris.score());
+
+		self.time += dt;
+		self.tetris.tick(dt);
+		self.check(lines, score);
+	}
+
+	fn react_to(&mut self, msg: Msg) {
+		if self.is_over() {
+			return;
+		}
+
+		let (lines, score) = (self.tetris.lines(), self.tetris.score());
+
+		self.tetris.react_to(msg);
+		self.check(lines, score);
+	}
+
+	fn hud(&self) -> Vec<String> {
+		let mut hud = vec!(
+			format!("Ultra {}", format_time(self.limit)),
+			format_time(self.remaining()),
+			format!("score {}", self.tetris.score()),
+			format!("lines {}", self.tetris.lines()),
+		);
+
+		// the breakdown and the leaderboard once the time is up
+		if self.finished {
+			hud.push(String::new());
+			for (name, (count, score)) in CLEARS.iter().zip(&self.clears) {
+				hud.push(format!("{name} {count} for {score}"));
+			}
+
+			hud.push(String::new());
+			for (i, record) in self.leaderboard.iter().enumerate() {
+				let mark = if self.place == Some(i) { ">" } else { " " };
+				hud.push(format!("{mark}{:>2} {:>7} {:>4}", i + 1, record.score, record.lines));
+			}
+		} else if let Some(best) = self.leaderboard.first() {
+			hud.push(format!("best {}", best.score));
+		}
+
+		hud
+	}
+
+	fn is_over(&self) -> bool {
+		self.finished
+	}
+
+	fn status(&self) -> Option<String> {
+		if !self.finished {
+			return None;
+		}
+
+		let end = if self.tetris.is_over() { "Game over" } else { "Time's up" };
+		let place = match self.place {
+			Some(place) => format!(", place {}", place + 1),
+			None => String::new(),
+		};
+		Some(format!("{end}, score {}{place}", self.tetris.score()))
+	}
+}
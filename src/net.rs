@@ -0,0 +1,430 @@
+use {
+	crate::{
+		color::Color::{
+			self,
+			*,
+		},
+		game::Msg,
+		tetris::{
+			Coord,
+			Tetris,
+			Tile,
+		},
+		versus::ATTACK,
+	},
+	rand::random,
+	std::{
+		io::{
+			self,
+			Error,
+			ErrorKind,
+			Read,
+			Write,
+		},
+		net::{
+			TcpListener,
+			TcpStream,
+		},
+		sync::mpsc::{
+			self,
+			Receiver,
+			TryRecvError,
+		},
+		thread,
+		time::Duration,
+	},
+};
+
+
+pub const DEFAULT_ADDR: &str = "127.0.0.1:7878";
+
+// number of blocks on the map, 10 by 16
+const BLOCKS: usize = 160;
+
+// the first byte of each packet
+const BOARD: u8 = 0;
+const ATTACK_TAG: u8 = 1;
+const GAME_OVER: u8 = 2;
+
+// sent by both peers before any packet, it's raised whenever packets change,
+// it's above the tags so the first packet of a build without it doesn't pass for it
+const PROTOCOL: u8 = 3;
+// time the opponent has to tell its protocol
+const HANDSHAKE: Duration = Duration::from_secs(5);
+
+
+// what peers are telling each other
+//
+//	Board		0, 160 pairs of bytes in map_iter order, the color of the block
+//				and the letter of its piece, themes color pieces by their letters
+//	Attack		1, rows of garbage, hole column
+//	GameOver	2
+#[derive(Debug, Clone, PartialEq)]
+pub enum Packet {
+	Board(Vec<Tile>),
+	Attack(u8, u8),
+	GameOver,
+}
+
+impl Packet {
+	pub fn write_to(&self, writer: &mut impl Write) -> io::Result<()> {
+		let mut bytes = vec!();
+
+		match self {
+			Packet::Board(tiles) => {
+				bytes.push(BOARD);
+				for tile in tiles {
+					// letters that aren't ascii are sent as unknown ones
+					let kind = if tile.kind.is_ascii_graphic() { tile.kind as u8 } else { b'?' };
+					bytes.extend([color_to_byte(tile.color), kind]);
+				}
+			}
+			Packet::Attack(rows, hole) =>
+				bytes.extend([ATTACK_TAG, *rows, *hole]),
+			Packet::GameOver =>
+				bytes.push(GAME_OVER),
+		};
+
+		writer.write_all(&bytes)
+	}
+
+	pub fn read_from(reader: &mut impl Read) -> io::Result<Packet> {
+		let mut tag = [0; 1];
+		reader.read_exact(&mut tag)?;
+
+		match tag[0] {
+			BOARD => {
+				let mut bytes = [0; BLOCKS * 2];
+				reader.read_exact(&mut bytes)?;
+
+				let tiles = bytes
+					.chunks(2)
+					.map(|pair| match (byte_to_color(pair[0]), pair[1]) {
+						(Some(color), kind) if kind.is_ascii_graphic() =>
+							Some(Tile { color, kind: kind as char, locked: None }),
+						_ => None,
+					})
+					.collect::<Option<Vec<Tile>>>()
+					.ok_or_else(|| invalid("unknown block"))?;
+
+				Ok(Packet::Board(tiles))
+			}
+			ATTACK_TAG => {
+				let mut bytes = [0; 2];
+				reader.read_exact(&mut bytes)?;
+				if bytes[1] > 9 {
+					return Err(invalid("hole is out of the map"));
+				}
+				Ok(Packet::Attack(bytes[0], bytes[1]))
+			}
+			GAME_OVER => Ok(Packet::GameOver),
+			_ => Err(invalid("unknown packet")),
+		}
+	}
+}
+
+
+fn invalid(msg: &str) -> Error {
+	Error::new(ErrorKind::InvalidData, msg)
+}
+
+// colors are sent as their numbers in Color::all(), then Grb and Non
+fn color_to_byte(color: Color) -> u8 {
+	match color {
+		Grb => 8,
+		Non => 9,
+		_ => Color::all().iter().position(|c| *c == color).unwrap() as u8,
+	}
+}
+
+fn byte_to_color(byte: u8) -> Option<Color> {
+	match byte {
+		0..=7 => Some(Color::all()[byte as usize]),
+		8 => Some(Grb),
+		9 => Some(Non),
+		_ => None,
+	}
+}
+
+
+// connection with the opponent, packets are read in the background
+pub struct Peer {
+	stream: TcpStream,
+	inbox: Receiver<Packet>,
+}
+
+impl Peer {
+	// waits for the opponent to join
+	pub fn host(addr: &str) -> io::Result<Peer> {
+		let listener = TcpListener::bind(addr)?;
+		let (stream, _) = listener.accept()?;
+		Peer::from_stream(stream)
+	}
+
+	pub fn join(addr: &str) -> io::Result<Peer> {
+		Peer::from_stream(TcpStream::connect(addr)?)
+	}
+
+	// hosts or joins without blocking, the peer is sent once it's ready
+	pub fn connect(host: bool, addr: String) -> Receiver<io::Result<Peer>> {
+		let (sender, receiver) = mpsc::channel();
+
+		thread::spawn(move || {
+			let peer = if host { Peer::host(&addr) } else { Peer::join(&addr) };
+			_ = sender.send(peer);
+		});
+
+		receiver
+	}
+
+	fn from_stream(mut stream: TcpStream) -> io::Result<Peer> {
+		stream.set_nodelay(true)?;
+		handshake(&mut stream)?;
+
+		let mut reader = stream.try_clone()?;
+		let (sender, inbox) = mpsc::channel();
+
+		// the channel gets closed as soon as the connection is lost
+		thread::spawn(move || {
+			while let Ok(packet) = Packet::read_from(&mut reader) {
+				if sender.send(packet).is_err() {
+					break;
+				}
+			}
+		});
+
+		Ok(Peer { stream, inbox })
+	}
+
+	pub fn send(&mut self, packet: &Packet) -> io::Result<()> {
+		packet.write_to(&mut self.stream)
+	}
+
+	// returns packets received since the last call
+	// and false if the connection is lost
+	pub fn receive(&self) -> (Vec<Packet>, bool) {
+		let mut packets = vec!();
+
+		loop {
+			match self.inbox.try_recv() {
+				Ok(packet) => packets.push(packet),
+				Err(TryRecvError::Empty) => return (packets, true),
+				Err(TryRecvError::Disconnected) => return (packets, false),
+			}
+		}
+	}
+}
+
+// peers of different versions would misread each other's packets
+fn handshake(stream: &mut TcpStream) -> io::Result<()> {
+	stream.write_all(&[PROTOCOL])?;
+
+	let mut protocol = [0; 1];
+	stream.set_read_timeout(Some(HANDSHAKE))?;
+	stream.read_exact(&mut protocol)?;
+	stream.set_read_timeout(None)?;
+
+	match protocol[0] {
+		PROTOCOL => Ok(()),
+		_ => Err(invalid("the opponent runs another version of the game")),
+	}
+}
+
+
+// the local game against the opponent's one on the other end of the peer
+pub struct Online {
+	board: Tetris,
+	// the last board received from the opponent
+	remote: Vec<Tile>,
+	// the last board sent to the opponent
+	sent: Vec<Tile>,
+	remote_over: bool,
+	connected: bool,
+	peer: Peer,
+}
+
+impl Online {
+	pub fn new(peer: Peer) -> Online {
+		Online {
+			board: Tetris::new(),
+			remote: vec!(Tile::from(Non); BLOCKS),
+			sent: vec!(),
+			remote_over: false,
+			connected: true,
+			peer,
+		}
+	}
+
+	// advances the local clock and handles what the opponent sent
+	pub fn tick(&mut self, dt: Duration) {
+		let (packets, connected) = self.peer.receive();
+		self.connected &= connected;
+		let over = self.board.is_over();
+
+		for packet in packets {
+			match packet {
+				Packet::Board(tiles) => self.remote = tiles,
+				Packet::Attack(rows, hole) =>
+					_ = self.board.add_garbage(rows as usize, &[hole as usize]),
+				Packet::GameOver => self.remote_over = true,
+			}
+		}
+
+		// garbage may top the board out, the opponent has to know it
+		if self.board.is_over() && !over {
+			self.share(self.board.lines());
+		}
+
+		if self.is_over() {
+			return;
+		}
+
+		let lines = self.board.lines();
+		self.board.tick(dt);
+		self.share(lines);
+	}
+
+	pub fn react_to(&mut self, msg: Msg) {
+		if self.is_over() {
+			return;
+		}
+
+		let lines = self.board.lines();
+		self.board.react_to(msg);
+		self.share(lines);
+	}
+
+	// sends garbage for the lines burned since the board had given lines,
+	// the board itself if it has changed and the game over once it happens
+	fn share(&mut self, lines: usize) {
+		if !self.connected {
+			return;
+		}
+
+		let mut packets = vec!();
+
+		let rows = ATTACK[(self.board.lines() - lines).min(4)];
+		if rows > 0 {
+			packets.push(Packet::Attack(rows as u8, random::<u8>() % 10));
+		}
+
+		// the opponent doesn't fade blocks, they are sent without lock times
+		let tiles = self.board
+			.map_iter()
+			.map(|(_, tile)| Tile { locked: None, ..tile })
+			.collect();
+		if tiles != self.sent {
+			packets.push(Packet::Board(tiles));
+		}
+
+		if self.board.is_over() {
+			packets.push(Packet::GameOver);
+		}
+
+		for packet in packets {
+			if self.peer.send(&packet).is_err() {
+				self.connected = false;
+				return;
+			}
+			if let Packet::Board(tiles) = packet {
+				self.sent = tiles;
+			}
+		}
+	}
+
+	// the match ends when somebody tops out or leaves
+	pub fn is_over(&self) -> bool {
+		self.board.is_over() || self.remote_over || !self.connected
+	}
+
+	// true if the local player has won, none if the match goes on
+	pub fn won(&self) -> Option<bool> {
+		match (self.board.is_over(), self.remote_over, self.connected) {
+			(true, _, _) => Some(false),
+			(false, true, _) | (false, _, false) => Some(true),
+			_ => None,
+		}
+	}
+
+	pub fn board(&self) -> &Tetris {
+		&self.board
+	}
+
+	// blocks of the opponent's board in map_iter order
+	pub fn remote_iter(&self) -> impl Iterator<Item = (Coord<usize>, Tile)> + '_ {
+		self.remote
+			.iter()
+			.enumerate()
+			.map(|(i, tile)| (Coord { x: i % 10, y: i / 10 }, *tile))
+	}
+}
+
+
+#[cfg(test)]
+mod tests {
+	use super::*;
+
+	// the host and the stream of a client connected to it
+	fn pair() -> (thread::JoinHandle<io::Result<Peer>>, TcpStream) {
+		let listener = TcpListener::bind("127.0.0.1:0").unwrap();
+		let addr = listener.local_addr().unwrap();
+		let host = thread::spawn(move || Peer::from_stream(listener.accept()?.0));
+		(host, TcpStream::connect(addr).unwrap())
+	}
+
+	#[test]
+	fn same_protocol() {
+		let (host, client) = pair();
+		assert!(Peer::from_stream(client).is_ok());
+		assert!(host.join().unwrap().is_ok());
+	}
+
+	// an old build sends its board right away
+	#[test]
+	fn other_protocol() {
+		let (host, mut client) = pair();
+		Packet::Board(vec!(Tile::from(Non); BLOCKS)).write_to(&mut client).unwrap();
+		let err = host.join().unwrap().err().unwrap();
+		assert_eq!(err.kind(), ErrorKind::InvalidData);
+	}
+
+	fn round_trip(packet: &Packet) -> io::Result<Packet> {
+		let mut bytes = vec!();
+		packet.write_to(&mut bytes)?;
+		Packet::read_from(&mut bytes.as_slice())
+	}
+
+	#[test]
+	fn packets() {
+		let mut tiles = vec!(Tile::from(Non); BLOCKS);
+		tiles[0] = Tile { color: Grb, kind: 'G', locked: None };
+		tiles[1] = Tile { color: Color::all()[3], kind: 'T', locked: None };
+		tiles[BLOCKS - 1] = Tile { color: Color::all()[7], kind: 'n', locked: None };
+
+		for packet in [Packet::Board(tiles), Packet::Attack(4, 9), Packet::GameOver] {
+			assert_eq!(round_trip(&packet).unwrap(), packet);
+		}
+
+		// letters that aren't ascii come back as unknown ones
+		let mut tiles = vec!(Tile::from(Non); BLOCKS);
+		tiles[0] = Tile { color: Grb, kind: 'ж', locked: None };
+		let Ok(Packet::Board(tiles)) = round_trip(&Packet::Board(tiles)) else { panic!() };
+		assert_eq!(tiles[0].kind, '?');
+	}
+
+	#[test]
+	fn broken_packets() {
+		let read = |bytes: &[u8]| Packet::read_from(&mut &bytes[..]).unwrap_err().kind();
+		assert_eq!(read(&[7]), ErrorKind::InvalidData);
+		assert_eq!(read(&[ATTACK_TAG, 2, 10]), ErrorKind::InvalidData);
+		assert_eq!(read(&[ATTACK_TAG, 2]), ErrorKind::UnexpectedEof);
+		assert_eq!(read(&[BOARD, 9, b'.']), ErrorKind::UnexpectedEof);
+
+		// a whole board with an unknown color
+		let mut board = vec!(BOARD, 10, b'.');
+		for _ in 1..BLOCKS {
+			board.extend([9, b'.']);
+		}
+		assert_eq!(read(&board), ErrorKind::InvalidData);
+	}
+}
@@ -0,0 +1,155 @@
+use {
+	iced::{
+		pick_list, slider, text_input, Alignment, Column, Element, Length,
+		PickList, Radio, Row, Slider, Text, TextInput,
+	},
+	crate::{
+		game::Msg,
+		settings::{
+			Resolution,
+			Settings,
+			Window,
+		},
+		theme::{
+			self,
+			Theme,
+		},
+	},
+};
+
+
+// a single setting changed on the screen
+#[derive(Debug, Clone, PartialEq)]
+pub enum Change {
+	Window(Window),
+	Resolution(Resolution),
+	Das(u32),
+	Arr(u32),
+	Sdf(u32),
+	Ruleset(String),
+	Theme(String),
+}
+
+
+// screen where settings are changed, every change is applied and saved at once
+pub struct Options {
+	settings: Settings,
+	status: String,
+	// resolutions offered, the current one is among them even if it's not common
+	resolutions: Vec<Resolution>,
+	resolution_state: pick_list::State<Resolution>,
+	das_state: slider::State,
+	arr_state: slider::State,
+	sdf_state: slider::State,
+	ruleset_state: text_input::State,
+	theme_state: text_input::State,
+}
+
+impl Options {
+	pub fn new(settings: Settings) -> Options {
+		let mut resolutions = Resolution::COMMON.to_vec();
+		if !resolutions.contains(&settings.display.resolution) {
+			resolutions.push(settings.display.resolution);
+		}
+
+		Options {
+			settings,
+			// changes aren't saved until a broken file is fixed
+			status: Settings::read().err().unwrap_or_default(),
+			resolutions,
+			resolution_state: pick_list::State::new(),
+			das_state: slider::State::new(),
+			arr_state: slider::State::new(),
+			sdf_state: slider::State::new(),
+			ruleset_state: text_input::State::new(),
+			theme_state: text_input::State::new(),
+		}
+	}
+
+	pub fn settings(&self) -> &Settings {
+		&self.settings
+	}
+
+	pub fn update(&mut self, change: Change) {
+		let settings = &mut self.settings;
+		let theme = match &change {
+			Change::Theme(name) => Theme::load(name).err(),
+			_ => None,
+		};
+		// borders of the window can't be changed while it's open
+		let borders = matches!(change, Change::Window(window)
+			if (window == Window::Undecorated) != (settings.display.window == Window::Undecorated));
+
+		match change {
+			Change::Window(window) => settings.display.window = window,
+			Change::Resolution(resolution) => settings.display.resolution = resolution,
+			Change::Das(das) => settings.handling.das = das,
+			Change::Arr(arr) => settings.handling.arr = arr,
+			Change::Sdf(sdf) => settings.handling.sdf = sdf,
+			Change::Ruleset(ruleset) => settings.gameplay.ruleset = ruleset,
+			Change::Theme(theme) => settings.theme = theme,
+		}
+
+		self.status = match self.settings.save() {
+			Err(err) => format!("Unable to save settings: {err}"),
+			Ok(()) if borders => String::from("Saved, borders change on the next start"),
+			Ok(()) => match theme {
+				Some(err) => format!("Saved, but the theme can't be loaded: {err}"),
+				None => String::from("Saved"),
+			},
+		};
+	}
+
+	pub fn view(&mut self) -> Element<'_, Msg> {
+		let settings = &self.settings;
+		let window = Window::ALL.iter().fold(Row::new().spacing(20), |row, &window| {
+			row.push(Radio::new(window, window.to_string(), Some(settings.display.window),
+				|window| Msg::Setting(Change::Window(window))))
+		});
+		let resolution = PickList::new(&mut self.resolution_state, &self.resolutions[..],
+				Some(settings.display.resolution),
+				|resolution| Msg::Setting(Change::Resolution(resolution)));
+		let das = Slider::new(&mut self.das_state, 0..=500, settings.handling.das,
+				|das| Msg::Setting(Change::Das(das)))
+			.step(1);
+		let arr = Slider::new(&mut self.arr_state, 0..=200, settings.handling.arr,
+				|arr| Msg::Setting(Change::Arr(arr)))
+			.step(1);
+		let sdf = Slider::new(&mut self.sdf_state, 1..=40, settings.handling.sdf,
+				|sdf| Msg::Setting(Change::Sdf(sdf)))
+			.step(1);
+		let ruleset = TextInput::new(&mut self.ruleset_state, "classic", &settings.gameplay.ruleset,
+				|ruleset| Msg::Setting(Change::Ruleset(ruleset)))
+			.padding(5);
+		let theme = TextInput::new(&mut self.theme_state, theme::BUILT_IN[0], &settings.theme,
+				|theme| Msg::Setting(Change::Theme(theme)))
+			.padding(5);
+
+		Column::new()
+			.width(Length::Fill)
+			.max_width(700)
+			.spacing(20)
+			.push(Text::new("Settings").size(40))
+			.push(setting("Window", String::new(), window.into()))
+			.push(setting("Resolution", String::new(), resolution.into()))
+			.push(setting("DAS", format!("{} ms", settings.handling.das), das.into()))
+			.push(setting("ARR", format!("{} ms", settings.handling.arr), arr.into()))
+			.push(setting("Soft drop factor", format!("{}x", settings.handling.sdf), sdf.into()))
+			.push(setting("Ruleset", String::new(), ruleset.into()))
+			.push(setting("Theme", theme::BUILT_IN.join(", "), theme.into()))
+			.push(Text::new(&self.status))
+			.push(Text::new("Escape to go back"))
+			.into()
+	}
+}
+
+
+// the name of the setting, the widget it's changed with and its value
+fn setting<'a>(name: &str, value: String, widget: Element<'a, Msg>) -> Row<'a, Msg> {
+	Row::new()
+		.spacing(20)
+		.align_items(Alignment::Center)
+		.push(Text::new(name).width(Length::Units(200)))
+		.push(Column::new().width(Length::Units(300)).push(widget))
+		.push(Text::new(value))
+}
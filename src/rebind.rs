@@ -0,0 +1,143 @@
+use {
+	iced::keyboard::{
+		KeyCode,
+		Modifiers,
+	},
+	crate::controls::{
+		Action,
+		Bindings,
+		Controls,
+		Key,
+		Preset,
+	},
+};
+
+
+// screen where keys are bound to actions by pressing them,
+// every change is saved at once
+pub struct Rebind {
+	controls: Controls,
+	// keys of the second player are edited
+	second: bool,
+	// the action under the cursor
+	cursor: usize,
+	// the action waits for a key to be pressed
+	listening: bool,
+	// the preset the next press of P gives
+	preset: usize,
+	status: String,
+}
+
+impl Default for Rebind {
+	fn default() -> Rebind {
+		Rebind::new()
+	}
+}
+
+impl Rebind {
+	pub fn new() -> Rebind {
+		// keys aren't saved until a broken file is fixed
+		let (controls, status) = match Controls::read() {
+			Ok(controls) => (controls, String::new()),
+			Err(err) => (Controls::default(), err),
+		};
+
+		Rebind {
+			controls,
+			second: false,
+			cursor: 0,
+			listening: false,
+			preset: 0,
+			status,
+		}
+	}
+
+	pub fn press(&mut self, code: KeyCode, modifiers: Modifiers) {
+		let action = Action::ALL[self.cursor];
+
+		if self.listening {
+			// ctrl is pressed before the key it goes with
+			if matches!(code, KeyCode::LControl | KeyCode::RControl) {
+				return;
+			}
+
+			let key = Key::new(code, modifiers);
+			self.listening = false;
+			if !key.is_named() {
+				self.status = format!("{key} can't be bound");
+				return;
+			}
+
+			let other = match self.second {
+				true => "main",
+				false => "second",
+			};
+			self.status = match self.controls.bind(self.second, action, key) {
+				true => format!("{key} is bound to {action}, the {other} player doesn't have it anymore"),
+				false => format!("{key} is bound to {action}"),
+			};
+			self.save();
+			return;
+		}
+
+		match code {
+			KeyCode::Up => self.cursor = self.cursor.checked_sub(1).unwrap_or(Action::ALL.len() - 1),
+			KeyCode::Down => self.cursor = (self.cursor + 1) % Action::ALL.len(),
+			KeyCode::Enter => self.listening = true,
+			KeyCode::Tab => self.second = !self.second,
+			KeyCode::Backspace => {
+				self.bindings().clear(action);
+				self.status = format!("{action} is unbound");
+				self.save();
+			}
+			KeyCode::P => {
+				let preset = Preset::ALL[self.preset];
+				self.preset = (self.preset + 1) % Preset::ALL.len();
+				self.controls.preset(self.second, preset);
+				self.status = format!("{preset} keys are set");
+				self.save();
+			}
+			_ => (),
+		}
+	}
+
+	// lines of text the screen is drawn with
+	pub fn lines(&self) -> Vec<String> {
+		let (player, bindings) = match self.second {
+			true => ("second player, on the left in versus", &self.controls.second),
+			false => ("main player", &self.controls.main),
+		};
+
+		let mut lines = vec!(format!("Controls of the {player}"), String::new());
+		for (i, &action) in Action::ALL.iter().enumerate() {
+			let mark = if i == self.cursor { ">" } else { " " };
+			let keys: Vec<String> = bindings.keys(action).iter().map(Key::to_string).collect();
+			lines.push(format!("{mark} {action:<24} {}", keys.join(", ")));
+		}
+
+		lines.push(String::new());
+		lines.push(match self.listening {
+			true => format!("Press a key for {}", Action::ALL[self.cursor]),
+			false => self.status.clone(),
+		});
+		lines.push(String::new());
+		lines.push(String::from("Up, Down to choose, Enter to bind, Backspace to unbind"));
+		lines.push(format!("Tab to switch players, P for {} keys", Preset::ALL[self.preset]));
+		lines.push(String::from("Escape to go back"));
+
+		lines
+	}
+
+	fn bindings(&mut self) -> &mut Bindings {
+		match self.second {
+			true => &mut self.controls.second,
+			false => &mut self.controls.main,
+		}
+	}
+
+	fn save(&mut self) {
+		if let Err(err) = self.controls.save() {
+			self.status = format!("Unable to save controls: {err}");
+		}
+	}
+}
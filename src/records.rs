@@ -0,0 +1,123 @@
+use {
+	serde::{
+		Deserialize,
+		Serialize,
+	},
+	std::{
+		collections::BTreeMap,
+		fs,
+		io,
+		path::PathBuf,
+		time::Duration,
+	},
+};
+
+
+// personal bests and leaderboards kept between runs in the data dir
+#[derive(Debug, Clone, Default, Serialize, Deserialize)]
+#[serde(default)]
+pub struct Records {
+	// the best sprint run by its target of lines
+	pub sprint: BTreeMap<String, SprintRecord>,
+	// the best ultra runs by their duration in seconds, the best first
+	pub ultra: BTreeMap<String, Vec<UltraRecord>>,
+}
+
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct SprintRecord {
+	// time of the run and times each SPLIT lines were burned at, in millis
+	pub time: u64,
+	pub splits: Vec<u64>,
+}
+
+impl SprintRecord {
+	pub fn time(&self) -> Duration {
+		Duration::from_millis(self.time)
+	}
+
+	pub fn split(&self, i: usize) -> Option<Duration> {
+		self.splits.get(i).copied().map(Duration::from_millis)
+	}
+}
+
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct UltraRecord {
+	pub score: usize,
+	pub lines: usize,
+}
+
+// number of the best ultra runs that are kept
+pub const LEADERBOARD: usize = 10;
+
+
+impl Records {
+	pub fn path() -> Option<PathBuf> {
+		dirs::data_dir().map(|dir| dir.join("tetris").join("records.toml"))
+	}
+
+	pub fn load() -> Records {
+		Records::read().unwrap_or_else(|err| {
+			eprintln!("{err}");
+			Records::default()
+		})
+	}
+
+	// there are no records yet if there is no file
+	pub fn read() -> Result<Records, String> {
+		let path = match Records::path() {
+			Some(path) => path,
+			None => return Ok(Records::default()),
+		};
+		let content = match fs::read_to_string(&path) {
+			Ok(content) => content,
+			Err(err) if err.kind() == io::ErrorKind::NotFound => return Ok(Records::default()),
+			Err(err) => return Err(format!("{}: {err}", path.display())),
+		};
+		toml::from_str(&content).map_err(|err| format!("{}: {}", path.display(), err.message()))
+	}
+
+	// returns the place of the run on the leaderboard if it got there
+	pub fn add_ultra(&mut self, limit: Duration, record: UltraRecord) -> Option<usize> {
+		let board = self.ultra.entry(limit.as_secs().to_string()).or_default();
+		let place = board.iter().position(|other| record.score > other.score).unwrap_or(board.len());
+
+		if place >= LEADERBOARD {
+			return None;
+		}
+		board.insert(place, record);
+		board.truncate(LEADERBOARD);
+		Some(place)
+	}
+
+	// a broken file is left for the player to fix rather than overwritten
+	pub fn save(&self) -> io::Result<()> {
+		let path = Records::path()
+			.ok_or_else(|| io::Error::new(io::ErrorKind::NotFound, "no data dir"))?;
+		if let Err(err) = Records::read() {
+			return Err(io::Error::new(io::ErrorKind::InvalidData, format!("{err}, fix or remove it")));
+		}
+		let content = toml::to_string(self)
+			.map_err(|err| io::Error::new(io::ErrorKind::InvalidData, err))?;
+
+		if let Some(dir) = path.parent() {
+			fs::create_dir_all(dir)?;
+		}
+		fs::write(path, content)
+	}
+}
+
+
+// minutes, seconds and millis like 1:02.345
+pub fn format_time(time: Duration) -> String {
+	let millis = time.as_millis();
+	format!("{}:{:02}.{:03}", millis / 60_000, millis / 1000 % 60, millis % 1000)
+}
+
+// difference from the record like +0.532 or -1.200
+pub fn format_delta(time: Duration, record: Duration) -> String {
+	let (sign, delta) = match time >= record {
+		true => ('+', time - record),
+		false => ('-', record - time),
+	};
+	format!("{sign}{}.{:03}", delta.as_secs(), delta.subsec_millis())
+}
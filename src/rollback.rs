@@ -0,0 +1,542 @@
+use {
+	crate::{
+		game::Msg,
+		versus::Versus,
+	},
+	rand::{
+		rngs::StdRng,
+		Rng,
+		SeedableRng,
+	},
+	std::{
+		collections::{
+			HashMap,
+			VecDeque,
+		},
+		io::{
+			self,
+			ErrorKind,
+		},
+		net::UdpSocket,
+		time::Duration,
+	},
+};
+
+
+// the simulation always goes on by steps of the same length
+pub const FRAME: Duration = Duration::from_micros(16_667);
+
+// how far the local side may run ahead of confirmed remote inputs
+const WINDOW: u32 = 30;
+// checksums are exchanged for every frame that is a multiple of it
+const CHECK_INTERVAL: u32 = 30;
+// the most of unacknowledged inputs that are sent at once
+const MAX_RESEND: usize = 64;
+
+// the first byte of each packet
+const INPUTS: u8 = 0;
+const CHECKSUM: u8 = 1;
+
+// input of a player on a single frame is a set of bits, one per event
+const EVENTS: [Msg; 7] = [
+	Msg::ShiftLft,
+	Msg::ShiftRgt,
+	Msg::Clockwise,
+	Msg::Counterclockwise,
+	Msg::Proceed,
+	Msg::Drop,
+	Msg::Hold,
+];
+
+
+// adds the event to the input of a single frame
+pub fn input_with(input: u8, msg: &Msg) -> u8 {
+	match EVENTS.iter().position(|event| event == msg) {
+		Some(bit) => input | 1 << bit,
+		None => input,
+	}
+}
+
+fn events_of(input: u8) -> impl Iterator<Item = Msg> {
+	EVENTS
+		.into_iter()
+		.enumerate()
+		.filter(move |(bit, _)| input & 1 << bit != 0)
+		.map(|(_, event)| event)
+}
+
+
+// anything that carries packets between two sessions, packets may be lost
+pub trait Link {
+	fn send(&mut self, packet: &[u8]);
+	fn receive(&mut self) -> Vec<Vec<u8>>;
+}
+
+
+// what sessions are telling each other
+//
+//	Inputs		0, first frame u32, ack u32, count u8, count inputs u8
+//	Checksum	1, frame u32, checksum u64
+//
+// all numbers are little endian, ack is the number of frames
+// whose inputs were received from the other side
+#[derive(Debug, PartialEq)]
+enum Packet {
+	Inputs(u32, u32, Vec<u8>),
+	Checksum(u32, u64),
+}
+
+impl Packet {
+	fn to_bytes(&self) -> Vec<u8> {
+		let mut bytes = vec!();
+
+		match self {
+			Packet::Inputs(first, ack, inputs) => {
+				bytes.push(INPUTS);
+				bytes.extend(first.to_le_bytes());
+				bytes.extend(ack.to_le_bytes());
+				bytes.push(inputs.len() as u8);
+				bytes.extend(inputs);
+			}
+			Packet::Checksum(frame, checksum) => {
+				bytes.push(CHECKSUM);
+				bytes.extend(frame.to_le_bytes());
+				bytes.extend(checksum.to_le_bytes());
+			}
+		};
+
+		bytes
+	}
+
+	fn from_bytes(bytes: &[u8]) -> Option<Packet> {
+		let u32_at = |i: usize| Some(u32::from_le_bytes(bytes.get(i..i + 4)?.try_into().ok()?));
+
+		match *bytes.first()? {
+			INPUTS => {
+				let count = *bytes.get(9)? as usize;
+				let inputs = bytes.get(10..10 + count)?.to_vec();
+				Some(Packet::Inputs(u32_at(1)?, u32_at(5)?, inputs))
+			}
+			CHECKSUM => {
+				let checksum = u64::from_le_bytes(bytes.get(5..13)?.try_into().ok()?);
+				Some(Packet::Checksum(u32_at(1)?, checksum))
+			}
+			_ => None,
+		}
+	}
+}
+
+
+// versus match where the remote inputs are predicted to be idle,
+// once the real ones arrive and differ the match is rolled back
+// to the first mispredicted frame and simulated again
+pub struct Rollback<L: Link> {
+	link: L,
+	// index of the local player in the match
+	local: usize,
+	// number of the frame that will be simulated next
+	frame: u32,
+	// the match before the frame is simulated
+	state: Versus,
+	// states before the frames that may still be rolled back
+	saved: VecDeque<(u32, Versus)>,
+	local_inputs: Vec<u8>,
+	// inputs are confirmed for frames before remote_inputs.len()
+	remote_inputs: Vec<u8>,
+	// number of local inputs known by the other side
+	remote_ack: u32,
+	// the last frame whose checksum was computed
+	checked: u32,
+	local_sums: HashMap<u32, u64>,
+	remote_sums: HashMap<u32, u64>,
+	// the first frame on which the sides have diverged
+	desync: Option<u32>,
+	rollbacks: usize,
+}
+
+impl<L: Link> Rollback<L> {
+	// both sides have to use the same seed and different players
+	pub fn new(link: L, local: usize, seed: u64) -> Rollback<L> {
+		Rollback {
+			link,
+			local,
+			frame: 0,
+			state: Versus::seeded(seed),
+			saved: VecDeque::new(),
+			local_inputs: vec!(),
+			remote_inputs: vec!(),
+			remote_ack: 0,
+			checked: 0,
+			local_sums: HashMap::new(),
+			remote_sums: HashMap::new(),
+			desync: None,
+			rollbacks: 0,
+		}
+	}
+
+	// simulates the next frame with the given local input
+	// returns false if it has to wait for the other side
+	pub fn update(&mut self, input: u8) -> bool {
+		self.poll();
+
+		let advanced = self.frame - self.confirmed().min(self.frame) < WINDOW;
+		if advanced {
+			self.local_inputs.push(input);
+			self.saved.push_back((self.frame, self.state.clone()));
+			self.simulate();
+		}
+
+		self.check();
+		self.send_inputs();
+		advanced
+	}
+
+	// exchanges packets without simulating anything
+	pub fn sync(&mut self) {
+		self.poll();
+		self.check();
+		self.send_inputs();
+	}
+
+	// handles all packets received since the last call
+	fn poll(&mut self) {
+		let mut rollback_to: Option<u32> = None;
+
+		for bytes in self.link.receive() {
+			match Packet::from_bytes(&bytes) {
+				Some(Packet::Inputs(first, ack, inputs)) => {
+					self.remote_ack = self.remote_ack.max(ack);
+
+					for (frame, input) in (first..).zip(inputs) {
+						// only the next unconfirmed input is taken,
+						// the rest will be sent again
+						if frame != self.confirmed() {
+							continue;
+						}
+						// idle was predicted for the simulated frames
+						if frame < self.frame && input != 0 {
+							rollback_to = Some(rollback_to.unwrap_or(frame).min(frame));
+						}
+						self.remote_inputs.push(input);
+					}
+				}
+				Some(Packet::Checksum(frame, checksum)) => {
+					self.remote_sums.insert(frame, checksum);
+					self.compare(frame);
+				}
+				None => (),
+			}
+		}
+
+		if let Some(frame) = rollback_to {
+			self.rollback(frame);
+		}
+	}
+
+	// restores the state before the frame and simulates it all again
+	fn rollback(&mut self, frame: u32) {
+		let pos = self.saved
+			.iter()
+			.position(|(saved, _)| *saved == frame)
+			.expect("rolled back state must be saved");
+
+		let target = self.frame;
+		self.frame = frame;
+		self.state = self.saved[pos].1.clone();
+		self.saved.truncate(pos);
+
+		while self.frame < target {
+			self.saved.push_back((self.frame, self.state.clone()));
+			self.simulate();
+		}
+
+		self.rollbacks += 1;
+	}
+
+	// applies inputs of the current frame to the state
+	fn simulate(&mut self) {
+		let frame = self.frame as usize;
+		let mut inputs = [0; 2];
+		inputs[self.local] = self.local_inputs[frame];
+		inputs[1 - self.local] = self.remote_inputs.get(frame).copied().unwrap_or(0);
+
+		// players go in the same order on both sides
+		for (player, input) in inputs.into_iter().enumerate() {
+			for event in events_of(input) {
+				self.state.react_to(player, event);
+			}
+		}
+
+		self.state.tick(FRAME);
+		self.frame += 1;
+	}
+
+	// computes checksums of frames that can't be rolled back anymore
+	// and forgets the states before them
+	fn check(&mut self) {
+		let last = self.confirmed().min(self.frame);
+
+		while self.checked + CHECK_INTERVAL <= last {
+			self.checked += CHECK_INTERVAL;
+			let frame = self.checked;
+
+			let checksum = match self.saved.iter().find(|(saved, _)| *saved == frame) {
+				Some((_, state)) => state.checksum(),
+				None => self.state.checksum(),
+			};
+
+			self.local_sums.insert(frame, checksum);
+			self.link.send(&Packet::Checksum(frame, checksum).to_bytes());
+			self.compare(frame);
+		}
+
+		while self.saved.front().is_some_and(|(saved, _)| *saved < last) {
+			self.saved.pop_front();
+		}
+	}
+
+	fn compare(&mut self, frame: u32) {
+		if let (Some(local), Some(remote)) =
+			(self.local_sums.get(&frame), self.remote_sums.get(&frame))
+		{
+			if local != remote && self.desync.is_none_or(|desync| frame < desync) {
+				self.desync = Some(frame);
+			}
+		}
+	}
+
+	// sends all local inputs the other side doesn't know yet
+	fn send_inputs(&mut self) {
+		let first = self.remote_ack as usize;
+		let last = self.local_inputs.len().min(first + MAX_RESEND);
+
+		if first < last {
+			let inputs = self.local_inputs[first..last].to_vec();
+			let packet = Packet::Inputs(first as u32, self.confirmed(), inputs);
+			self.link.send(&packet.to_bytes());
+		}
+	}
+
+	// number of frames whose remote inputs are known
+	pub fn confirmed(&self) -> u32 {
+		self.remote_inputs.len() as u32
+	}
+
+	pub fn frame(&self) -> u32 {
+		self.frame
+	}
+
+	pub fn state(&self) -> &Versus {
+		&self.state
+	}
+
+	pub fn local(&self) -> usize {
+		self.local
+	}
+
+	pub fn desync(&self) -> Option<u32> {
+		self.desync
+	}
+
+	pub fn rollbacks(&self) -> usize {
+		self.rollbacks
+	}
+
+	pub fn link_mut(&mut self) -> &mut L {
+		&mut self.link
+	}
+}
+
+
+// the real link between two app instances
+pub struct UdpLink {
+	socket: UdpSocket,
+}
+
+impl UdpLink {
+	pub fn connect(local: &str, remote: &str) -> io::Result<UdpLink> {
+		let socket = UdpSocket::bind(local)?;
+		socket.connect(remote)?;
+		socket.set_nonblocking(true)?;
+
+		Ok(UdpLink { socket })
+	}
+}
+
+impl Link for UdpLink {
+	fn send(&mut self, packet: &[u8]) {
+		// lost packets are sent again anyway
+		_ = self.socket.send(packet);
+	}
+
+	fn receive(&mut self) -> Vec<Vec<u8>> {
+		let mut packets = vec!();
+		let mut buf = [0; 512];
+
+		loop {
+			match self.socket.recv(&mut buf) {
+				Ok(len) => packets.push(buf[..len].to_vec()),
+				// the other side may be not started yet
+				Err(err) if err.kind() == ErrorKind::ConnectionRefused => continue,
+				Err(_) => return packets,
+			}
+		}
+	}
+}
+
+
+// simulated link, packets are moved between two of them by the harness
+#[derive(Default)]
+pub struct Loopback {
+	sent: Vec<Vec<u8>>,
+	// packets with the frame on which they will be delivered
+	in_flight: Vec<(u32, Vec<u8>)>,
+	inbox: Vec<Vec<u8>>,
+}
+
+impl Link for Loopback {
+	fn send(&mut self, packet: &[u8]) {
+		self.sent.push(packet.to_vec());
+	}
+
+	fn receive(&mut self) -> Vec<Vec<u8>> {
+		std::mem::take(&mut self.inbox)
+	}
+}
+
+
+// what happened during the loopback run
+#[derive(Debug)]
+pub struct Report {
+	pub frames: u32,
+	pub rollbacks: [usize; 2],
+	pub desync: Option<u32>,
+	// whether both sides ended up with the same match
+	pub synced: bool,
+}
+
+// plays a match between two sessions with random inputs over links
+// delaying packets by latency frames and losing them with probability loss
+pub fn loopback(frames: u32, latency: u32, loss: f64, seed: u64) -> Report {
+	let mut rng = StdRng::seed_from_u64(seed);
+	let mut sessions = [
+		Rollback::new(Loopback::default(), 0, seed),
+		Rollback::new(Loopback::default(), 1, seed),
+	];
+
+	let mut clock = 0;
+	let settled = |sessions: &[Rollback<Loopback>; 2]| sessions
+		.iter()
+		.all(|session| session.frame() >= frames && session.confirmed() >= frames);
+
+	// sessions stop after the given frames and wait for each other
+	while !settled(&sessions) && clock < frames * 10 {
+		for session in &mut sessions {
+			if session.frame() < frames {
+				let input =
+					if rng.gen_ratio(1, 8) {
+						1 << rng.gen_range(0..EVENTS.len())
+					} else {
+						0
+					};
+				session.update(input);
+			} else {
+				session.sync();
+			}
+		}
+
+		for (from, to) in [(0, 1), (1, 0)] {
+			let sent = std::mem::take(&mut sessions[from].link_mut().sent);
+			let in_flight = &mut sessions[to].link_mut().in_flight;
+
+			for packet in sent {
+				if !rng.gen_bool(loss) {
+					in_flight.push((clock + latency, packet));
+				}
+			}
+
+			let link = sessions[to].link_mut();
+			let (due, later) = std::mem::take(&mut link.in_flight)
+				.into_iter()
+				.partition(|(at, _)| *at <= clock);
+			link.in_flight = later;
+			link.inbox.extend(due.into_iter().map(|(_, packet)| packet));
+		}
+
+		clock += 1;
+	}
+
+	let desync = sessions
+		.iter()
+		.filter_map(|session| session.desync())
+		.min();
+
+	Report {
+		frames: sessions[0].frame().min(sessions[1].frame()),
+		rollbacks: [sessions[0].rollbacks(), sessions[1].rollbacks()],
+		desync,
+		synced: sessions[0].frame() == sessions[1].frame() &&
+				sessions[0].state().checksum() == sessions[1].state().checksum(),
+	}
+}
+
+
+#[cfg(test)]
+mod tests {
+	use super::*;
+
+	#[test]
+	fn inputs() {
+		assert_eq!(events_of(0).count(), 0);
+		assert_eq!(input_with(0, &Msg::Tick), 0);
+
+		for event in EVENTS {
+			assert_eq!(events_of(input_with(0, &event)).collect::<Vec<_>>(), vec!(event));
+		}
+
+		let input = EVENTS.iter().fold(0, input_with);
+		assert_eq!(events_of(input).collect::<Vec<_>>(), EVENTS.to_vec());
+	}
+
+	#[test]
+	fn packets() {
+		let packets = [
+			Packet::Inputs(0, 0, vec!()),
+			Packet::Inputs(u32::MAX, 1234, vec!(0, 1, 0x7f, 3)),
+			Packet::Inputs(5, 6, vec!(1; MAX_RESEND)),
+			Packet::Checksum(90, u64::MAX - 1),
+		];
+		for packet in packets {
+			assert_eq!(Packet::from_bytes(&packet.to_bytes()), Some(packet));
+		}
+
+		// cut or unknown packets are dropped
+		let bytes = Packet::Inputs(1, 2, vec!(3, 4)).to_bytes();
+		assert_eq!(Packet::from_bytes(&bytes[..bytes.len() - 1]), None);
+		assert_eq!(Packet::from_bytes(&Packet::Checksum(1, 2).to_bytes()[..12]), None);
+		assert_eq!(Packet::from_bytes(&[]), None);
+		assert_eq!(Packet::from_bytes(&[7, 0, 0, 0, 0]), None);
+	}
+
+	fn assert_synced(report: Report) {
+		assert!(report.synced, "{report:?}");
+		assert_eq!(report.desync, None, "{report:?}");
+	}
+
+	#[test]
+	fn loopback_perfect_link() {
+		assert_synced(loopback(600, 0, 0.0, 0));
+	}
+
+	#[test]
+	fn loopback_lossy_link() {
+		assert_synced(loopback(3600, 6, 0.1, 0));
+	}
+
+	#[test]
+	fn loopback_bad_link() {
+		for seed in 0..3 {
+			assert_synced(loopback(2000, 20, 0.5, seed));
+		}
+	}
+}
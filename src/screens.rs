@@ -0,0 +1,251 @@
+use {
+	iced::keyboard::KeyCode,
+	crate::{
+		game::Play,
+		lobby::Lobby,
+		modes::{self, Dig, Hidden, Marathon, Master, Sprint, Ultra},
+		options::Options,
+		rebind::Rebind,
+		records::{
+			format_time,
+			Records,
+		},
+		settings::Settings,
+		tetris::{Bot, Setup, Tetris, Weights},
+		versus::Versus,
+	},
+	rand::random,
+	std::fmt,
+};
+
+
+// what the app shows, screens are kept in a stack and the top one gets the keys,
+// escape goes back to the one under it
+#[allow(clippy::large_enum_variant)]
+pub enum Screen {
+	Title,
+	Menu(Menu<Entry>),
+	Modes(Menu<Choice>),
+	Options(Options),
+	Rebind(Rebind),
+	Lobby(Lobby),
+	// lines of the records
+	Scores(Vec<String>),
+	// the choice the game was started by, it's played again on restart
+	Play(Play, Option<Choice>),
+	// the game stays under the pause and the results
+	Pause(Menu<Paused>),
+	Results(Vec<String>),
+}
+
+
+// entries chosen by arrows and enter
+pub struct Menu<T> {
+	title: &'static str,
+	entries: Vec<T>,
+	cursor: usize,
+	// shown under the entries, like why the entry can't be chosen
+	pub status: String,
+}
+
+impl<T: Copy + fmt::Display> Menu<T> {
+	pub fn new(title: &'static str, entries: Vec<T>) -> Menu<T> {
+		Menu { title, entries, cursor: 0, status: String::new() }
+	}
+
+	// returns the entry once it's chosen
+	pub fn press(&mut self, code: KeyCode) -> Option<T> {
+		match code {
+			KeyCode::Up => self.cursor = self.cursor.checked_sub(1).unwrap_or(self.entries.len() - 1),
+			KeyCode::Down => self.cursor = (self.cursor + 1) % self.entries.len(),
+			KeyCode::Enter | KeyCode::Space => return Some(self.entries[self.cursor]),
+			_ => (),
+		}
+		None
+	}
+
+	// lines of text the screen is drawn with
+	pub fn lines(&self) -> Vec<String> {
+		let mut lines = vec!(String::from(self.title), String::new());
+		for (i, entry) in self.entries.iter().enumerate() {
+			let mark = if i == self.cursor { ">" } else { " " };
+			lines.push(format!("{mark} {entry}"));
+		}
+
+		lines.push(String::new());
+		lines.push(self.status.clone());
+		lines
+	}
+}
+
+
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub enum Entry {
+	Play,
+	Modes,
+	Online,
+	Scores,
+	Options,
+	Controls,
+	Quit,
+}
+
+impl Entry {
+	pub const ALL: [Entry; 7] = [
+		Entry::Play,
+		Entry::Modes,
+		Entry::Online,
+		Entry::Scores,
+		Entry::Options,
+		Entry::Controls,
+		Entry::Quit,
+	];
+}
+
+impl fmt::Display for Entry {
+	fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+		let name = match self {
+			Entry::Play => "Play",
+			Entry::Modes => "Modes",
+			Entry::Online => "Online match",
+			Entry::Scores => "High scores",
+			Entry::Options => "Options",
+			Entry::Controls => "Controls",
+			Entry::Quit => "Quit",
+		};
+		f.pad(name)
+	}
+}
+
+
+// games that are started from the menu
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub enum Choice {
+	// endless game by the ruleset of the settings
+	Classic,
+	Marathon,
+	Endless,
+	Sprint,
+	Ultra,
+	Dig,
+	Survival,
+	Master,
+	Invisible,
+	Fading,
+	Versus,
+	Watch,
+}
+
+impl Choice {
+	pub const ALL: [Choice; 12] = [
+		Choice::Classic,
+		Choice::Marathon,
+		Choice::Endless,
+		Choice::Sprint,
+		Choice::Ultra,
+		Choice::Dig,
+		Choice::Survival,
+		Choice::Master,
+		Choice::Invisible,
+		Choice::Fading,
+		Choice::Versus,
+		Choice::Watch,
+	];
+
+	// a new game with a random seed, it fails if the ruleset can't be loaded
+	pub fn play(&self, settings: &Settings) -> Result<Play, String> {
+		let setup = Setup { seed: random(), ..Setup::default() };
+
+		let play = match self {
+			Choice::Classic => Play::Single(Tetris::with(settings.gameplay.setup(random())?)),
+			Choice::Marathon => Play::Mode(Box::new(Marathon::new(Some(modes::marathon::GOAL), setup))),
+			Choice::Endless => Play::Mode(Box::new(Marathon::new(None, setup))),
+			Choice::Sprint => Play::Mode(Box::new(Sprint::new(modes::sprint::TARGET, setup))),
+			Choice::Ultra => Play::Mode(Box::new(Ultra::new(modes::ultra::LIMIT, setup))),
+			Choice::Dig => Play::Mode(Box::new(Dig::new(modes::dig::ROWS, None, setup))),
+			Choice::Survival =>
+				Play::Mode(Box::new(Dig::new(modes::dig::ROWS, Some(modes::dig::INTERVAL), setup))),
+			Choice::Master => Play::Mode(Box::new(Master::new(setup))),
+			Choice::Invisible => Play::Mode(Box::new(Hidden::new(None, setup))),
+			Choice::Fading => Play::Mode(Box::new(Hidden::new(Some(modes::hidden::FADE), setup))),
+			Choice::Versus => Play::Versus(Versus::new()),
+			Choice::Watch => Play::Watch(Tetris::new(), Bot::new(Weights::default())),
+		};
+		Ok(play)
+	}
+}
+
+impl fmt::Display for Choice {
+	fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+		match self {
+			Choice::Classic => write!(f, "Classic"),
+			Choice::Marathon => write!(f, "Marathon, {} lines", modes::marathon::GOAL),
+			Choice::Endless => write!(f, "Endless marathon"),
+			Choice::Sprint => write!(f, "Sprint, {} lines", modes::sprint::TARGET),
+			Choice::Ultra => write!(f, "Ultra, {} minutes", modes::ultra::LIMIT.as_secs() / 60),
+			Choice::Dig => write!(f, "Dig, {} rows", modes::dig::ROWS),
+			Choice::Survival => write!(f, "Survival"),
+			Choice::Master => write!(f, "Master"),
+			Choice::Invisible => write!(f, "Invisible"),
+			Choice::Fading => write!(f, "Fading"),
+			Choice::Versus => write!(f, "Versus, two players"),
+			Choice::Watch => write!(f, "Watch the bot"),
+		}
+	}
+}
+
+
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub enum Paused {
+	Resume,
+	Restart,
+	Quit,
+}
+
+impl fmt::Display for Paused {
+	fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+		let name = match self {
+			Paused::Resume => "Resume",
+			Paused::Restart => "Restart",
+			Paused::Quit => "Quit to the menu",
+		};
+		f.pad(name)
+	}
+}
+
+
+// lines of the high scores screen
+pub fn scores(records: Result<Records, String>) -> Vec<String> {
+	let mut lines = vec!(String::from("High scores"), String::new());
+
+	let records = match records {
+		Ok(records) => records,
+		Err(err) => {
+			lines.push(err);
+			lines.push(String::new());
+			lines.push(String::from("Escape to go back"));
+			return lines;
+		}
+	};
+
+	for (target, record) in &records.sprint {
+		lines.push(format!("Sprint, {target} lines   {}", format_time(record.time())));
+	}
+
+	for (secs, board) in &records.ultra {
+		let minutes = secs.parse::<u64>().unwrap_or_default() / 60;
+		lines.push(String::new());
+		lines.push(format!("Ultra, {minutes} minutes"));
+		for (i, record) in board.iter().enumerate() {
+			lines.push(format!("{:>2}. {:>8} {:>5} lines", i + 1, record.score, record.lines));
+		}
+	}
+
+	if records.sprint.is_empty() && records.ultra.is_empty() {
+		lines.push(String::from("No records yet"));
+	}
+
+	lines.push(String::new());
+	lines.push(String::from("Escape to go back"));
+	lines
+}
@@ -0,0 +1,224 @@
+// everything the player may tune, kept in the config dir as
+//
+//	[display]
+//	window = "fullscreen"		# windowed, undecorated or fullscreen
+//	resolution = { width = 1280, height = 720 }
+//
+//	[handling]
+//	das = 167
+//	arr = 33
+//	sdf = 20
+//	...
+
+use {
+	crate::tetris::{
+		Ruleset,
+		Setup,
+	},
+	serde::{
+		Deserialize,
+		Serialize,
+	},
+	std::{
+		fmt,
+		fs,
+		io,
+		path::PathBuf,
+		time::Duration,
+	},
+};
+
+
+#[derive(Debug, Clone, PartialEq, Default, Serialize, Deserialize)]
+#[serde(default)]
+pub struct Settings {
+	pub display: Display,
+	pub handling: Handling,
+	pub gameplay: Gameplay,
+	// name of a built in theme or a theme file, the classic one if it's empty
+	pub theme: String,
+}
+
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+#[serde(from = "DisplayFile")]
+pub struct Display {
+	pub window: Window,
+	// size of the window when it doesn't cover the screen
+	pub resolution: Resolution,
+}
+
+impl Default for Display {
+	fn default() -> Display {
+		Display {
+			window: Window::Fullscreen,
+			resolution: Resolution { width: 1280, height: 720 },
+		}
+	}
+}
+
+// the display section as it may be written, older files have fullscreen = true or false
+// instead of the window
+#[derive(Deserialize)]
+struct DisplayFile {
+	window: Option<Window>,
+	resolution: Option<Resolution>,
+	fullscreen: Option<bool>,
+}
+
+impl From<DisplayFile> for Display {
+	fn from(file: DisplayFile) -> Display {
+		let default = Display::default();
+		Display {
+			window: file.window.unwrap_or(match file.fullscreen {
+				Some(false) => Window::Windowed,
+				_ => default.window,
+			}),
+			resolution: file.resolution.unwrap_or(default.resolution),
+		}
+	}
+}
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(rename_all = "snake_case")]
+pub enum Window {
+	Windowed,
+	// a window of the resolution without the title bar and borders,
+	// they are only taken away at startup
+	#[serde(alias = "borderless")]
+	Undecorated,
+	Fullscreen,
+}
+
+impl Window {
+	pub const ALL: [Window; 3] = [Window::Windowed, Window::Undecorated, Window::Fullscreen];
+}
+
+impl fmt::Display for Window {
+	fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+		let name = match self {
+			Window::Windowed => "Windowed",
+			Window::Undecorated => "Undecorated",
+			Window::Fullscreen => "Fullscreen",
+		};
+		f.pad(name)
+	}
+}
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize, Deserialize)]
+pub struct Resolution {
+	pub width: u32,
+	pub height: u32,
+}
+
+impl Resolution {
+	// the ones offered on the settings screen, any other may be written to the file
+	pub const COMMON: [Resolution; 8] = [
+		Resolution { width: 800, height: 600 },
+		Resolution { width: 1024, height: 768 },
+		Resolution { width: 1280, height: 720 },
+		Resolution { width: 1366, height: 768 },
+		Resolution { width: 1600, height: 900 },
+		Resolution { width: 1920, height: 1080 },
+		Resolution { width: 2560, height: 1440 },
+		Resolution { width: 720, height: 1280 },
+	];
+}
+
+impl fmt::Display for Resolution {
+	fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+		write!(f, "{}x{}", self.width, self.height)
+	}
+}
+
+// how held keys repeat their actions, times are in millis
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+#[serde(default)]
+pub struct Handling {
+	// delayed auto shift, time a shift key is held before it repeats
+	pub das: u32,
+	// auto repeat rate, interval of the repeated shifts, zero moves to the wall at once
+	pub arr: u32,
+	// soft drop factor, how many times faster tetriminos fall while it's held
+	pub sdf: u32,
+}
+
+impl Default for Handling {
+	fn default() -> Handling {
+		Handling { das: 167, arr: 33, sdf: 20 }
+	}
+}
+
+impl Handling {
+	pub fn das(&self) -> Duration {
+		Duration::from_millis(self.das.into())
+	}
+
+	pub fn arr(&self) -> Duration {
+		Duration::from_millis(self.arr.into())
+	}
+}
+
+#[derive(Debug, Clone, PartialEq, Default, Serialize, Deserialize)]
+#[serde(default)]
+pub struct Gameplay {
+	// file of the ruleset single games are played by, the classic one if it's empty
+	pub ruleset: String,
+}
+
+impl Gameplay {
+	// the classic setup is used if there is no ruleset
+	pub fn setup(&self, seed: u64) -> Result<Setup, String> {
+		if self.ruleset.is_empty() {
+			return Ok(Setup { seed, ..Setup::default() });
+		}
+
+		Ruleset::load(self.ruleset.as_ref())
+			.map(|ruleset| ruleset.setup(seed))
+			.map_err(|err| format!("{}: {err}", self.ruleset))
+	}
+}
+
+
+impl Settings {
+	pub fn path() -> Option<PathBuf> {
+		dirs::config_dir().map(|dir| dir.join("tetris").join("settings.toml"))
+	}
+
+	// the defaults are used if the file is missing or broken, a broken one is reported
+	pub fn load() -> Settings {
+		Settings::read().unwrap_or_else(|err| {
+			eprintln!("{err}");
+			Settings::default()
+		})
+	}
+
+	// the defaults if there is no file yet
+	pub fn read() -> Result<Settings, String> {
+		let path = match Settings::path() {
+			Some(path) => path,
+			None => return Ok(Settings::default()),
+		};
+		let content = match fs::read_to_string(&path) {
+			Ok(content) => content,
+			Err(err) if err.kind() == io::ErrorKind::NotFound => return Ok(Settings::default()),
+			Err(err) => return Err(format!("{}: {err}", path.display())),
+		};
+		toml::from_str(&content).map_err(|err| format!("{}: {}", path.display(), err.message()))
+	}
+
+	// a broken file is left for the player to fix rather than overwritten
+	pub fn save(&self) -> io::Result<()> {
+		let path = Settings::path()
+			.ok_or_else(|| io::Error::new(io::ErrorKind::NotFound, "no config dir"))?;
+		if let Err(err) = Settings::read() {
+			return Err(io::Error::new(io::ErrorKind::InvalidData, format!("{err}, fix or remove it")));
+		}
+		let content = toml::to_string(self)
+			.map_err(|err| io::Error::new(io::ErrorKind::InvalidData, err))?;
+
+		if let Some(dir) = path.parent() {
+			fs::create_dir_all(dir)?;
+		}
+		fs::write(path, content)
+	}
+}
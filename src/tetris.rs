@@ -1,88 +1,260 @@
+mod bot;
+mod env;
+mod fumen;
 mod geometry;
 mod map;
+mod piece;
 mod random;
+mod ruleset;
+// the old score file, the records have taken its place
+#[allow(dead_code)]
 mod score;
+mod search;
 mod tetrimino;
 
+pub use {
+	bot::{
+		Bot,
+		Features,
+		Weights,
+	},
+	env::{
+		Action,
+		Encoding,
+		Env,
+		Observation,
+		Shaping,
+		StepInfo,
+	},
+	fumen::{
+		Fumen,
+		FumenError,
+		Operation,
+		Page,
+		Rotation,
+	},
+	geometry::Coord,
+	map::{
+		BoardError,
+		Map,
+		Tile,
+	},
+	piece::{
+		pentominoes,
+		piece_set,
+		tetrominoes,
+		trominoes,
+		Piece,
+	},
+	random::Randomizer,
+	ruleset::{
+		Ruleset,
+		RulesetError,
+	},
+	search::Placement,
+};
+
 use {
 	crate::game::Msg,
 	map::{
-		Map,
 		MapIter,
+		HEIGHT,
+		WIDTH,
 	},
-	geometry::{
-		Direction::{
-			self,
-			*,
-		},
-	},
+	geometry::Direction::*,
 	tetrimino::{
 		Generator,
 		Tetrimino,
 	},
+	rand::random,
 	std::{
 		time::{
 			Duration,
 		},
 		cmp::min,
+		collections::hash_map::DefaultHasher,
+		fmt,
+		hash::{
+			Hash,
+			Hasher,
+		},
 	},
 };
 
 
-// number of burned lines to go to next level
+// number of burned lines to go to next level,
+// after the last one levels go on each LEVEL_STEP lines
 const LEVEL_LINES: [usize; 10] =
 	[20, 40, 60, 80, 100, 120, 140, 160, 180, 200];
-// time in millis after which tetrimino descents
+const LEVEL_STEP: usize = 20;
+// time in millis after which tetrimino descents,
+// the speed doesn't grow after the last one
 const LEVEL_TIMES: [u64; 10] =
 	[1000, 850, 700, 600, 500, 400, 300, 250, 220, 190];
 
 
+// everything the game is set up with before it starts
+#[derive(Debug, Clone)]
+pub struct Setup {
+	pub seed: u64,
+	pub width: usize,
+	pub height: usize,
+	pub randomizer: Randomizer,
+	// serial numbers of tetriminos are indexes here
+	pub pieces: Vec<Piece>,
+}
+
+impl Default for Setup {
+	// random seed and the classic game
+	fn default() -> Setup {
+		Setup {
+			seed: random(),
+			width: WIDTH,
+			height: HEIGHT,
+			randomizer: Randomizer::Classic,
+			pieces: tetrominoes(),
+		}
+	}
+}
+
+
+// what happened when the last tetrimino died
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash)]
+pub struct Lock {
+	pub id: u8,
+	pub lines: usize,
+	// T was turned into its place, three corners around its center are filled
+	pub tspin: bool,
+	// nothing was left on the map
+	pub perfect: bool,
+}
+
+
+#[derive(Clone)]
 pub struct Tetris {
 	level: usize,		// level is counted from zero
 	score: usize,
 	lines: usize,
+	// number of dead tetriminos
+	pieces: usize,
+	// true since the stack went over the top
+	over: bool,
+	// time passed since the last descent
+	elapsed: Duration,
 	generator: Generator,
 	tetrimino: Option<Tetrimino>,
+	// serial number of the tetrimino put aside
+	hold: Option<u8>,
+	// tetrimino may be held only once until it dies
+	held: bool,
+	// the last successful move of the tetrimino was a rotation
+	spun: bool,
+	last: Option<Lock>,
 	map: Map,
 }
 
+impl Default for Tetris {
+	fn default() -> Tetris {
+		Tetris::new()
+	}
+}
+
 impl Tetris {
 	// makes all setup for the game
 	pub fn new() -> Tetris {
-		let generator = Generator::new();
+		Tetris::with(Setup::default())
+	}
+
+	// the same seed and the same events give the same game
+	pub fn seeded(seed: u64) -> Tetris {
+		Tetris::with(Setup { seed, ..Setup::default() })
+	}
+
+	pub fn with(setup: Setup) -> Tetris {
+		let generator = Generator::new(setup.seed, setup.randomizer, setup.pieces);
 		let tetrimino = None;
-		let map = Map::new(generator.map_bg());
+		let map = Map::new(setup.width, setup.height);
 
 		Tetris {
 			level: 0,
 			score: 0,
 			lines: 0,
+			pieces: 0,
+			over: false,
+			elapsed: Duration::ZERO,
 			generator,
 			tetrimino,
+			hold: None,
+			held: false,
+			spun: false,
+			last: None,
 			map,
 		}
 	}
 
 	// generating, descenting or killing tetrimino
 	pub fn proceed(&mut self) {
+		if self.over {
+			return;
+		}
+
 		// println!("{:?}", self.tetrimino);
 		// generating a new one tetrimino
 		if self.tetrimino.is_none() {
 			// println!("new tetrimino");
-			self.tetrimino = Some(self.generator.gen());
-		}			
+			self.tetrimino = self.generator.gen(self.map.top());
+		}
+
+		// the held one is the last to be played once the fixed queue runs out
+		if self.tetrimino.is_none() {
+			self.tetrimino = self.hold.take().map(|id| self.generator.make(id, self.map.top()));
+		}
+
+		// the fixed queue has run out
+		if self.tetrimino.is_none() {
+			return;
+		}
 
 		// unwrap won't panic
 		let tetrimino = self.tetrimino.as_mut().unwrap();
 
 		// trying to descent existing tetrimino
-		if !tetrimino.shift(Dwn, &mut self.map) {
+		if tetrimino.shift(Dwn, &mut self.map) {
+			self.spun = false;
+		} else {
+			let tspin = self.spun && tetrimino.is_t() && {
+				let center = tetrimino.center();
+				[(-1, -1), (1, -1), (-1, 1), (1, 1)]
+					.iter()
+					.filter(|(x, y)| self.map.blocked(center.x + x, center.y + y))
+					.count() >= 3
+			};
+			let id = tetrimino.id;
+
+			// tetrimino can't be left above the map
+			let height = self.map.height() as i8;
+			self.over = tetrimino.iter().any(|tile| tile.y >= height);
+
 			// leave tetrimino's corpse on the map
 			self.map.kill();
 			self.tetrimino = None;
+			self.held = false;
+			self.spun = false;
+			self.pieces += 1;
+
+			if self.over {
+				return;
+			}
 
 			// burn completed lines
 			let lines = self.map.burn();
+			self.last = Some(Lock {
+				id,
+				lines,
+				tspin,
+				perfect: lines > 0 && self.map.is_empty(),
+			});
+
 			// increase lines, score, level
 			self.lines += lines;
 			self.score += match lines {
@@ -91,19 +263,61 @@ impl Tetris {
 				2 => 100 * (self.level + 1),
 				3 => 300 * (self.level + 1),
 				4 => 1200 * (self.level + 1),
-				_ => unreachable!(),
+				// only longer pieces than tetrominoes burn more
+				_ => 1200 * (lines - 3) * (self.level + 1),
 			};
 
-			if self.lines >= LEVEL_LINES[self.level] {
+			while self.lines >= level_lines(self.level) {
 				self.level += 1;
 			}
 		}
 	}
 
+	// generates the next tetrimino at once if there is no alive one
+	pub fn spawn(&mut self) {
+		if self.tetrimino.is_none() {
+			self.proceed();
+		}
+	}
+
+	// advances the game clock, tetrimino descents once level time is passed
+	pub fn tick(&mut self, dt: Duration) {
+		self.elapsed += dt;
+		self.advance(dt);
+
+		if self.elapsed >= self.level_time() {
+			self.elapsed = Duration::ZERO;
+			self.proceed();
+		}
+	}
+
+	// advances the game clock only, the gravity is up to the caller
+	pub fn advance(&mut self, dt: Duration) {
+		self.map.advance(dt);
+	}
+
+	// descents alive tetrimino as low as it goes without killing it,
+	// returns the number of rows it has fallen
+	pub fn fall(&mut self) -> usize {
+		let mut rows = 0;
+		if let Some(tetrimino) = self.tetrimino.as_mut() {
+			while tetrimino.shift(Dwn, &mut self.map) {
+				self.spun = false;
+				rows += 1;
+			}
+		}
+		rows
+	}
+
+	// whether there is the alive tetrimino
+	pub fn is_alive(&self) -> bool {
+		self.tetrimino.is_some()
+	}
+
 	// handling the given event related to the game mechanics
 	pub fn react_to(&mut self, msg: Msg) {
 		// very soon a new one tetrimino will be generated
-		if self.tetrimino.is_none() {
+		if self.over || self.tetrimino.is_none() {
 			return;
 		}
 		// println!("{:?}\n", msg);
@@ -114,30 +328,235 @@ impl Tetris {
 		match msg {
             Msg::Proceed => self.proceed(),
             Msg::ShiftRgt =>
-            	_ = tetrimino.shift(Rgt, &mut self.map),
+            	if tetrimino.shift(Rgt, &mut self.map) { self.spun = false },
             Msg::ShiftLft =>
-            	_ = tetrimino.shift(Lft, &mut self.map),
+            	if tetrimino.shift(Lft, &mut self.map) { self.spun = false },
             Msg::Clockwise =>
-            	_ = tetrimino.rotate(true, &mut self.map),
+            	if tetrimino.rotate(true, &mut self.map) { self.spun = true },
             Msg::Counterclockwise =>
-            	_ = tetrimino.rotate(false, &mut self.map),
+            	if tetrimino.rotate(false, &mut self.map) { self.spun = true },
+            Msg::Drop => self.hard_drop(),
+            Msg::Hold => self.hold(),
             _ => unreachable!(),
         };
 	}
 
-	pub fn print_map(&self) {
-		for (coord, color) in self.map.iter() {
-			print!("{:?} ", color);
+	// descents alive tetrimino until it dies
+	pub fn hard_drop(&mut self) {
+		if let Some(tetrimino) = self.tetrimino.as_mut() {
+			while tetrimino.shift(Dwn, &mut self.map) {
+				self.spun = false;
+			}
+		}
+		self.proceed();
+	}
+
+	// puts alive tetrimino aside and takes the held one instead
+	pub fn hold(&mut self) {
+		if self.held {
+			return;
+		}
+
+		if self.tetrimino.is_none() {
+			return;
+		}
+
+		// there may be nothing to take instead when the fixed queue runs out
+		let next = match self.hold {
+			Some(id) => self.generator.make(id, self.map.top()),
+			None => match self.generator.gen(self.map.top()) {
+				Some(next) => next,
+				None => return,
+			},
+		};
+
+		// unwrap won't panic, it was checked above
+		let tetrimino = self.tetrimino.replace(next).unwrap();
+		self.map.remove();
+		self.hold = Some(tetrimino.id);
+		self.held = true;
+	}
+
+	// serial numbers of the next tetriminos
+	pub fn preview(&self) -> Vec<u8> {
+		self.generator.preview().collect()
+	}
+
+	// serial number of the held tetrimino
+	pub fn held(&self) -> Option<u8> {
+		self.hold
+	}
+
+	// blocks of the tetrimino with the given serial number
+	// moved so that the lower left corner of its bounds is at zero
+	// and how each of them is shown
+	pub fn shape_of(&self, id: u8) -> (Vec<Coord<i8>>, Tile) {
+		let tetrimino = self.generator.make(id, Coord { x: 0, y: 0 });
+		let bounds = tetrimino.bounds();
+
+		let blocks = tetrimino
+			.iter()
+			.map(|block| Coord {
+				x: block.x - bounds.x_axis.lhe,
+				y: block.y - bounds.y_axis.lhe,
+			})
+			.collect();
+
+		(blocks, Tile { color: tetrimino.color, kind: tetrimino.letter(), locked: None })
+	}
+
+	// pushes garbage up from the bottom, the alive tetrimino is lifted
+	// if it overlaps, returns false if the stack went over the top
+	// or the tetrimino was lifted above the map
+	pub fn add_garbage(&mut self, rows: usize, hole_columns: &[usize]) -> bool {
+		let mut survived = self.map.add_garbage(rows, hole_columns);
+
+		if let Some(tetrimino) = self.tetrimino.as_mut() {
+			let height = self.map.height() as i8;
+			if tetrimino.lift(&mut self.map) && tetrimino.iter().any(|tile| tile.y >= height) {
+				survived = false;
+			}
+		}
+
+		if !survived {
+			self.over = true;
+		}
+		survived
+	}
+
+	// the board without the alive tetrimino to be shared
+	pub fn fumen(&self) -> Result<Fumen, FumenError> {
+		let mut map = self.map.clone();
+		map.remove();
+		Ok(Fumen { pages: vec!(Page::new(&map)?) })
+	}
+
+	// number of rows with some garbage left in them
+	pub fn garbage_rows(&self) -> usize {
+		self.map.garbage_rows()
+	}
+
+	// every final position the alive tetrimino can reach
+	pub fn placements(&self) -> Vec<Placement> {
+		match &self.tetrimino {
+			Some(tetrimino) => search::placements(&self.map, tetrimino),
+			None => vec!(),
 		}
-		println!("\n");
 	}
 
 	// shows how long will tetrimino hang without descending
 	pub fn level_time(&self) -> Duration {
-		Duration::from_millis(LEVEL_TIMES[min(self.level, 9)])
+		Duration::from_millis(LEVEL_TIMES[min(self.level, LEVEL_TIMES.len() - 1)])
+	}
+
+	// digest of the whole game state, equal games have equal checksums
+	pub fn checksum(&self) -> u64 {
+		let mut hasher = DefaultHasher::new();
+
+		self.level.hash(&mut hasher);
+		self.score.hash(&mut hasher);
+		self.lines.hash(&mut hasher);
+		self.pieces.hash(&mut hasher);
+		self.over.hash(&mut hasher);
+		self.elapsed.hash(&mut hasher);
+		self.generator.hash(&mut hasher);
+		self.hold.hash(&mut hasher);
+		self.held.hash(&mut hasher);
+		self.spun.hash(&mut hasher);
+		self.last.hash(&mut hasher);
+		self.tetrimino.hash(&mut hasher);
+		self.map.hash(&mut hasher);
+
+		hasher.finish()
+	}
+
+	pub fn is_over(&self) -> bool {
+		self.over
 	}
 
-	pub fn map_iter(&self) -> MapIter {
+	// how the last tetrimino died
+	pub fn last_lock(&self) -> Option<Lock> {
+		self.last
+	}
+
+	// whether there are no tetriminos left to play with
+	pub fn exhausted(&self) -> bool {
+		self.tetrimino.is_none() && self.hold.is_none() && self.generator.preview().next().is_none()
+	}
+
+	// replaces blocks of the map with the board written as text
+	pub fn load_board(&mut self, board: &str) -> Result<(), BoardError> {
+		self.load_map(&board.parse()?)
+	}
+
+	// replaces blocks of the map with the board,
+	// blocks of pieces take their colors, unknown letters stay gray
+	pub fn load_map(&mut self, board: &Map) -> Result<(), BoardError> {
+		let mut board = board.clone();
+		for id in 0..self.kinds() {
+			let tetrimino = self.generator.make(id as u8, self.map.top());
+			board.paint(tetrimino.letter(), tetrimino.color);
+		}
+
+		self.map.load(&board)?;
+		if let Some(tetrimino) = self.tetrimino.as_mut() {
+			_ = tetrimino.lift(&mut self.map);
+		}
+		Ok(())
+	}
+
+	pub fn level(&self) -> usize {
+		self.level
+	}
+
+	pub fn lines(&self) -> usize {
+		self.lines
+	}
+
+	pub fn score(&self) -> usize {
+		self.score
+	}
+
+	pub fn pieces(&self) -> usize {
+		self.pieces
+	}
+
+	// number of different tetriminos
+	pub fn kinds(&self) -> usize {
+		self.generator.kinds()
+	}
+
+	pub fn width(&self) -> usize {
+		self.map.width()
+	}
+
+	pub fn height(&self) -> usize {
+		self.map.height()
+	}
+
+	// time the game has been played for, locked blocks are stamped with it
+	pub fn clock(&self) -> Duration {
+		self.map.clock()
+	}
+
+	pub fn map_iter(&self) -> MapIter<'_> {
 		self.map.iter()
 	}
 }
+
+
+// the map as text, the alive tetrimino is there too
+impl fmt::Display for Tetris {
+	fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+		self.map.fmt(f)
+	}
+}
+
+
+// number of burned lines to leave the level
+fn level_lines(level: usize) -> usize {
+	match LEVEL_LINES.get(level) {
+		Some(&lines) => lines,
+		None => LEVEL_LINES[LEVEL_LINES.len() - 1] + LEVEL_STEP * (level + 1 - LEVEL_LINES.len()),
+	}
+}
@@ -0,0 +1,184 @@
+use {
+	crate::game::Msg,
+	super::{
+		map::Map,
+		Tetris,
+	},
+	std::{
+		collections::VecDeque,
+		num::ParseFloatError,
+		str::FromStr,
+	},
+};
+
+
+// how much each feature of the map after placement is worth
+#[derive(Debug, Clone, Copy)]
+pub struct Weights {
+	pub height: f32,		// sum of column heights
+	pub lines: f32,			// burned lines
+	pub holes: f32,			// empty blocks under filled ones
+	pub bumpiness: f32,		// sum of height differences of neighbour columns
+	pub wells: f32,			// sum of depths of columns lower than both neighbours
+}
+
+impl Default for Weights {
+	fn default() -> Weights {
+		Weights {
+			height: -0.510066,
+			lines: 0.760666,
+			holes: -0.35663,
+			bumpiness: -0.184483,
+			wells: -0.1,
+		}
+	}
+}
+
+// five numbers separated by commas or spaces in the order of fields
+impl FromStr for Weights {
+	type Err = ParseFloatError;
+
+	fn from_str(string: &str) -> Result<Weights, ParseFloatError> {
+		let nums = string
+			.split(|c: char| c == ',' || c.is_whitespace())
+			.filter(|num| !num.is_empty())
+			.map(|num| num.parse::<f32>())
+			.collect::<Result<Vec<f32>, _>>()?;
+
+		let default = Weights::default();
+		let num = |i: usize, default: f32| nums.get(i).copied().unwrap_or(default);
+
+		Ok(Weights {
+			height: num(0, default.height),
+			lines: num(1, default.lines),
+			holes: num(2, default.holes),
+			bumpiness: num(3, default.bumpiness),
+			wells: num(4, default.wells),
+		})
+	}
+}
+
+impl Weights {
+	// the higher the better
+	pub fn evaluate(&self, map: &Map, lines: usize) -> f32 {
+		let features = Features::of(map);
+
+		self.height * features.height as f32 +
+		self.lines * lines as f32 +
+		self.holes * features.holes as f32 +
+		self.bumpiness * features.bumpiness as f32 +
+		self.wells * features.wells as f32
+	}
+}
+
+
+// standard features of the map, alive tetrimino is counted too
+#[derive(Debug, Clone, Copy, Default)]
+pub struct Features {
+	pub height: usize,
+	pub holes: usize,
+	pub bumpiness: usize,
+	pub wells: usize,
+}
+
+impl Features {
+	pub fn of(map: &Map) -> Features {
+		let (width, top) = (map.width(), map.height());
+		let heights: Vec<usize> = (0..width).map(|x| map.column_height(x)).collect();
+
+		let height = heights.iter().sum();
+
+		let holes = (0..width)
+			.map(|x| (0..heights[x]).filter(|y| !map.filled(x, *y)).count())
+			.sum();
+
+		let bumpiness = heights
+			.windows(2)
+			.map(|pair| pair[0].abs_diff(pair[1]))
+			.sum();
+
+		// walls are considered as high as the map
+		let wells = (0..width)
+			.map(|x| {
+				let lft = if x == 0 { top } else { heights[x - 1] };
+				let rgt = if x == width - 1 { top } else { heights[x + 1] };
+				lft.min(rgt).saturating_sub(heights[x])
+			})
+			.sum();
+
+		Features {
+			height,
+			holes,
+			bumpiness,
+			wells,
+		}
+	}
+}
+
+
+// plays the game by placing each tetrimino where weights say it's the best
+pub struct Bot {
+	weights: Weights,
+	// moves left to bring the tetrimino over its place
+	plan: VecDeque<Msg>,
+	// number of pieces locked before the tetrimino the plan is for,
+	// the clock may bring a new tetrimino in between moves
+	planned: Option<usize>,
+}
+
+impl Bot {
+	pub fn new(weights: Weights) -> Bot {
+		Bot {
+			weights,
+			plan: VecDeque::new(),
+			planned: None,
+		}
+	}
+
+	// makes a single move, once the tetrimino is at its place
+	// it's descented until it dies
+	pub fn play(&mut self, tetris: &mut Tetris) {
+		if tetris.is_over() {
+			return;
+		}
+
+		if tetris.tetrimino.is_none() {
+			tetris.proceed();
+			return;
+		}
+
+		if self.planned != Some(tetris.pieces()) {
+			self.plan = self.best(tetris).into();
+			self.planned = Some(tetris.pieces());
+		}
+
+		match self.plan.pop_front() {
+			Some(msg) => tetris.react_to(msg),
+			None => tetris.proceed(),
+		}
+	}
+
+	// true while there are moves left to bring the tetrimino over its place
+	pub fn busy(&self) -> bool {
+		!self.plan.is_empty()
+	}
+
+	// moves that bring the alive tetrimino to the best place
+	pub fn best(&self, tetris: &Tetris) -> Vec<Msg> {
+		let mut best = (f32::NEG_INFINITY, vec!());
+
+		for placement in tetris.placements() {
+			let mut map = tetris.map.clone();
+			map.put(&placement.tetrimino);
+			map.kill();
+			let lines = map.burn();
+
+			let score = self.weights.evaluate(&map, lines);
+			if score > best.0 {
+				best = (score, placement.moves);
+			}
+		}
+
+		best.1
+	}
+}
@@ -0,0 +1,222 @@
+use {
+	crate::game::Msg,
+	super::{
+		bot::Features,
+		tetrimino::PREVIEW,
+		Tetris,
+	},
+	std::time::Duration,
+};
+
+
+// everything an agent can do on a single step
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum Action {
+	Idle,
+	ShiftLft,
+	ShiftRgt,
+	Clockwise,
+	Counterclockwise,
+	SoftDrop,
+	HardDrop,
+	Hold,
+}
+
+impl Action {
+	// actions are numbered by their positions here
+	pub const ALL: [Action; 8] = [
+		Action::Idle,
+		Action::ShiftLft,
+		Action::ShiftRgt,
+		Action::Clockwise,
+		Action::Counterclockwise,
+		Action::SoftDrop,
+		Action::HardDrop,
+		Action::Hold,
+	];
+
+	pub fn from_index(index: usize) -> Option<Action> {
+		Action::ALL.get(index).copied()
+	}
+
+	fn msg(&self) -> Option<Msg> {
+		match self {
+			Action::Idle => None,
+			Action::ShiftLft => Some(Msg::ShiftLft),
+			Action::ShiftRgt => Some(Msg::ShiftRgt),
+			Action::Clockwise => Some(Msg::Clockwise),
+			Action::Counterclockwise => Some(Msg::Counterclockwise),
+			Action::SoftDrop => Some(Msg::Proceed),
+			Action::HardDrop => Some(Msg::Drop),
+			Action::Hold => Some(Msg::Hold),
+		}
+	}
+}
+
+
+// which parts of the game get into observations
+#[derive(Debug, Clone, Copy)]
+pub struct Encoding {
+	pub board: bool,	// dead blocks
+	pub piece: bool,	// blocks of alive tetrimino
+	pub queue: usize,	// number of the next tetriminos, up to PREVIEW
+	pub hold: bool,		// held tetrimino and whether it may be held now
+}
+
+impl Default for Encoding {
+	fn default() -> Encoding {
+		Encoding {
+			board: true,
+			piece: true,
+			queue: PREVIEW,
+			hold: true,
+		}
+	}
+}
+
+
+// what the agent sees, parts that aren't encoded are empty
+//
+//	board		all blocks by rows from the bottom, 1 if it's filled
+//	piece		all blocks in the same order, 1 if it's alive tetrimino
+//	queue		one per kind for each next tetrimino, 1 at its serial number
+//	hold		the same for held tetrimino, then 1 if hold is available
+#[derive(Debug, Clone, Default)]
+pub struct Observation {
+	pub board: Vec<f32>,
+	pub piece: Vec<f32>,
+	pub queue: Vec<f32>,
+	pub hold: Vec<f32>,
+}
+
+impl Observation {
+	// all parts one by one
+	pub fn flatten(&self) -> Vec<f32> {
+		[&self.board, &self.piece, &self.queue, &self.hold]
+			.into_iter()
+			.flatten()
+			.copied()
+			.collect()
+	}
+}
+
+
+// what happened on the step, reward is computed from it
+#[derive(Debug, Clone, Copy)]
+pub struct StepInfo {
+	pub action: Action,
+	pub lines: usize,		// burned on the step
+	pub score: usize,		// earned on the step
+	pub died: bool,			// whether tetrimino has died on the step
+	pub over: bool,
+	pub features: Features,
+}
+
+pub type Shaping = Box<dyn Fn(&StepInfo) -> f32 + Send>;
+
+
+// headless game driven by an agent step by step
+pub struct Env {
+	tetris: Tetris,
+	encoding: Encoding,
+	// the game clock advances by it on each step
+	step_time: Duration,
+	shaping: Shaping,
+}
+
+impl Env {
+	// reward is the earned score until other shaping is set
+	pub fn new(encoding: Encoding) -> Env {
+		Env {
+			tetris: Tetris::seeded(0),
+			encoding,
+			step_time: Duration::from_millis(100),
+			shaping: Box::new(|info| info.score as f32),
+		}
+	}
+
+	pub fn with_shaping(mut self, shaping: impl Fn(&StepInfo) -> f32 + Send + 'static) -> Env {
+		self.shaping = Box::new(shaping);
+		self
+	}
+
+	pub fn with_step_time(mut self, step_time: Duration) -> Env {
+		self.step_time = step_time;
+		self
+	}
+
+	pub fn reset(&mut self, seed: u64) -> Observation {
+		self.tetris = Tetris::seeded(seed);
+		self.tetris.proceed();
+		self.observe()
+	}
+
+	// returns what the agent sees after the action, reward and game over
+	pub fn step(&mut self, action: Action) -> (Observation, f32, bool) {
+		let (score, lines, pieces) =
+			(self.tetris.score, self.tetris.lines, self.tetris.pieces);
+
+		if let Some(msg) = action.msg() {
+			self.tetris.react_to(msg);
+		}
+		self.tetris.tick(self.step_time);
+
+		// the next tetrimino comes at once
+		if self.tetris.tetrimino.is_none() {
+			self.tetris.proceed();
+		}
+
+		let info = StepInfo {
+			action,
+			lines: self.tetris.lines - lines,
+			score: self.tetris.score - score,
+			died: self.tetris.pieces > pieces,
+			over: self.tetris.is_over(),
+			features: Features::of(&self.tetris.map),
+		};
+
+		(self.observe(), (self.shaping)(&info), info.over)
+	}
+
+	pub fn observe(&self) -> Observation {
+		let mut observation = Observation::default();
+		let map = &self.tetris.map;
+		let (width, height) = (map.width(), map.height());
+		let kinds = self.tetris.kinds();
+		let blocks = || (0..height).flat_map(|y| (0..width).map(move |x| (x, y)));
+
+		if self.encoding.board {
+			observation.board = blocks()
+				.map(|(x, y)| (map.filled(x, y) && !map.alive(x, y)) as u8 as f32)
+				.collect();
+		}
+
+		if self.encoding.piece {
+			observation.piece = blocks()
+				.map(|(x, y)| map.alive(x, y) as u8 as f32)
+				.collect();
+		}
+
+		for id in self.tetris.preview().into_iter().take(self.encoding.queue) {
+			observation.queue.extend(one_hot(Some(id), kinds));
+		}
+
+		if self.encoding.hold {
+			observation.hold.extend(one_hot(self.tetris.hold, kinds));
+			observation.hold.push(!self.tetris.held as u8 as f32);
+		}
+
+		observation
+	}
+
+	pub fn tetris(&self) -> &Tetris {
+		&self.tetris
+	}
+}
+
+
+fn one_hot(id: Option<u8>, kinds: usize) -> Vec<f32> {
+	(0..kinds as u8)
+		.map(|i| (Some(i) == id) as u8 as f32)
+		.collect()
+}
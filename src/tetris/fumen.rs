@@ -0,0 +1,623 @@
+// fumen is the way boards are shared by the community, for example
+//
+//	v115@9gF8DeF8DeF8DeF8NeAgH
+//
+// pages follow each other, each one has a field of 23 rows and a garbage row
+// under them, a piece placed on it and a comment, fields are written
+// as differences from the previous page after its piece was locked,
+// numbers are written in base 64 with the least significant digit first
+//
+// pages of any fumen are read, but games share only their current board
+// as a single page, they aren't recorded as replays
+
+use {
+	super::map::{
+		Map,
+		EMPTY,
+		GARBAGE,
+	},
+	std::{
+		fmt,
+		str::FromStr,
+	},
+};
+
+
+const VERSION: &str = "v115@";
+const DIGITS: &[u8] = b"ABCDEFGHIJKLMNOPQRSTUVWXYZabcdefghijklmnopqrstuvwxyz0123456789+/";
+// characters of comments, their numbers are written by four in base 96
+const COMMENT: &[u8] =
+	b" !\"#$%&'()*+,-./0123456789:;<=>?@ABCDEFGHIJKLMNOPQRSTUVWXYZ[\\]^_`abcdefghijklmnopqrstuvwxyz{|}~";
+
+const WIDTH: usize = 10;
+// rows of the field above the garbage row
+const ROWS: usize = 23;
+const BLOCKS: usize = (ROWS + 1) * WIDTH;
+
+// blocks by their numbers in fields, the last one is gray
+const KINDS: [char; 9] = [EMPTY, 'I', 'L', 'O', 'Z', 'T', 'J', 'S', GARBAGE];
+// longest comment that can be written
+const COMMENT_LEN: usize = 4095;
+
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum Rotation {
+	Reverse,
+	Right,
+	Spawn,
+	Left,
+}
+
+impl Rotation {
+	const ALL: [Rotation; 4] = [Rotation::Reverse, Rotation::Right, Rotation::Spawn, Rotation::Left];
+
+	// turns the block of the piece in its spawn state
+	fn turn(&self, (x, y): (i8, i8)) -> (i8, i8) {
+		match self {
+			Rotation::Spawn => (x, y),
+			Rotation::Right => (y, -x),
+			Rotation::Reverse => (-x, -y),
+			Rotation::Left => (-y, x),
+		}
+	}
+}
+
+
+// the tetromino placed on the page, its center is the one of SRS,
+// y is counted from the bottom row of the field
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct Operation {
+	pub kind: char,
+	pub rotation: Rotation,
+	pub x: i8,
+	pub y: i8,
+}
+
+impl Operation {
+	fn blocks(&self) -> impl Iterator<Item = (i8, i8)> + '_ {
+		let spawn: &[(i8, i8)] = match self.kind {
+			'I' => &[(0, 0), (-1, 0), (1, 0), (2, 0)],
+			'L' => &[(0, 0), (-1, 0), (1, 0), (1, 1)],
+			'O' => &[(0, 0), (1, 0), (0, 1), (1, 1)],
+			'Z' => &[(0, 0), (1, 0), (0, 1), (-1, 1)],
+			'T' => &[(0, 0), (-1, 0), (1, 0), (0, 1)],
+			'J' => &[(0, 0), (-1, 0), (1, 0), (-1, 1)],
+			'S' => &[(0, 0), (-1, 0), (0, 1), (1, 1)],
+			_ => &[],
+		};
+
+		spawn.iter().map(move |&block| {
+			let (x, y) = self.rotation.turn(block);
+			(self.x + x, self.y + y)
+		})
+	}
+
+	// O, I, S and Z are written with their centers moved in some rotations
+	fn offset(&self) -> (i8, i8) {
+		match (self.kind, self.rotation) {
+			('O', Rotation::Left) => (1, -1),
+			('O', Rotation::Reverse) => (1, 0),
+			('O', Rotation::Spawn) => (0, -1),
+			('I', Rotation::Reverse) => (1, 0),
+			('I', Rotation::Left) => (0, -1),
+			('S', Rotation::Spawn) => (0, -1),
+			('S', Rotation::Right) => (-1, 0),
+			('Z', Rotation::Spawn) => (0, -1),
+			('Z', Rotation::Left) => (1, 0),
+			_ => (0, 0),
+		}
+	}
+}
+
+
+#[derive(Debug, Clone, PartialEq)]
+pub struct Page {
+	// numbers of blocks from the top row, the garbage row is the last one
+	field: Vec<u8>,
+	pub operation: Option<Operation>,
+	// the piece is left on the field and lines are burned for the next page
+	pub lock: bool,
+	// then the garbage row is pushed up and the field is mirrored
+	pub rise: bool,
+	pub mirror: bool,
+	pub comment: String,
+}
+
+impl Page {
+	// the board is 10 blocks wide and fits the field, blocks that aren't
+	// tetrominoes are gray
+	pub fn new(board: &Map) -> Result<Page, FumenError> {
+		if board.width() != WIDTH {
+			return Err(FumenError::Width(board.width()));
+		}
+
+		let mut field = vec!(0; BLOCKS);
+		for y in 0..board.height() {
+			for x in 0..WIDTH {
+				let number = match KINDS.iter().position(|&kind| kind == board.kind(x, y)) {
+					Some(number) => number as u8,
+					None => 8,
+				};
+
+				if y >= ROWS && number != 0 {
+					return Err(FumenError::Height(board.height()));
+				}
+				if y < ROWS {
+					field[index(x, y as i8)] = number;
+				}
+			}
+		}
+
+		Ok(Page {
+			field,
+			operation: None,
+			lock: true,
+			rise: false,
+			mirror: false,
+			comment: String::new(),
+		})
+	}
+
+	// the field up to the topmost filled row, the piece isn't there
+	pub fn board(&self) -> Map {
+		let height = (0..ROWS)
+			.rev()
+			.find(|&y| (0..WIDTH).any(|x| self.field[index(x, y as i8)] != 0))
+			.map_or(1, |y| y + 1);
+
+		let mut board = Map::new(WIDTH, height);
+		for y in 0..height {
+			for x in 0..WIDTH {
+				board.set_kind(x, y, KINDS[self.field[index(x, y as i8)] as usize]);
+			}
+		}
+		board
+	}
+
+	// the field the next page is written against
+	fn next(&self) -> Vec<u8> {
+		let mut field = self.field.clone();
+		if !self.lock {
+			return field;
+		}
+
+		if let Some(operation) = self.operation {
+			let number = KINDS.iter().position(|&kind| kind == operation.kind).unwrap_or(8);
+			for (x, y) in operation.blocks() {
+				if (0..WIDTH as i8).contains(&x) && (-1..ROWS as i8).contains(&y) {
+					field[index(x as usize, y)] = number as u8;
+				}
+			}
+		}
+
+		// completed lines are burned, the garbage row stays
+		let mut rows: Vec<&[u8]> = field[..ROWS * WIDTH]
+			.chunks(WIDTH)
+			.filter(|row| row.contains(&0))
+			.collect();
+		let burned = ROWS - rows.len();
+		let empty = [0; WIDTH];
+		rows.splice(0..0, std::iter::repeat_n(&empty[..], burned));
+		let mut next: Vec<u8> = rows.concat();
+		next.extend_from_slice(&field[ROWS * WIDTH..]);
+
+		if self.rise {
+			next.drain(..WIDTH);
+			next.extend_from_slice(&empty);
+		}
+
+		if self.mirror {
+			for row in next[..ROWS * WIDTH].chunks_mut(WIDTH) {
+				row.reverse();
+			}
+		}
+
+		next
+	}
+}
+
+
+// pages of a fumen, it's parsed from the data with or without a link before it
+#[derive(Debug, Clone, PartialEq)]
+pub struct Fumen {
+	pub pages: Vec<Page>,
+}
+
+impl Fumen {
+	// pieces placed one by one or the ones of the quiz if nothing is placed
+	pub fn queue(&self) -> Vec<char> {
+		let placed: Vec<char> = self.pages
+			.iter()
+			.filter_map(|page| page.operation.map(|operation| operation.kind))
+			.collect();
+
+		match self.pages.first() {
+			Some(page) if placed.is_empty() && page.comment.starts_with("#Q=") => page.comment[3..]
+				.chars()
+				.filter(|kind| KINDS[1..8].contains(kind))
+				.collect(),
+			_ => placed,
+		}
+	}
+}
+
+impl FromStr for Fumen {
+	type Err = FumenError;
+
+	fn from_str(string: &str) -> Result<Fumen, FumenError> {
+		let data = match string.find(VERSION) {
+			Some(start) => &string[start + VERSION.len()..],
+			None => return Err(FumenError::Version),
+		};
+
+		let mut reader = Reader {
+			digits: data
+				.bytes()
+				.filter(|&byte| byte != b'?' && !byte.is_ascii_whitespace())
+				.map(|byte| DIGITS
+					.iter()
+					.position(|&digit| digit == byte)
+					.map(|digit| digit as u8)
+					.ok_or(FumenError::Character(byte as char)))
+				.collect::<Result<Vec<u8>, _>>()?,
+			at: 0,
+		};
+
+		let mut pages = vec!();
+		let mut field = vec!(0; BLOCKS);
+		let mut comment = String::new();
+		// pages left that keep the same field
+		let mut repeat = 0;
+
+		while !reader.is_empty() {
+			if repeat > 0 {
+				repeat -= 1;
+			} else {
+				let mut i = 0;
+				while i < BLOCKS {
+					let value = reader.read(2)?;
+					let (diff, count) = (value / BLOCKS, value % BLOCKS + 1);
+
+					if diff == 8 && count == BLOCKS {
+						repeat = reader.read(1)?;
+					}
+					for _ in 0..count.min(BLOCKS - i) {
+						field[i] = (field[i] as usize + diff)
+							.checked_sub(8)
+							.filter(|&number| number < KINDS.len())
+							.ok_or(FumenError::Field)? as u8;
+						i += 1;
+					}
+				}
+			}
+
+			let mut value = reader.read(3)?;
+			let mut take = |n: usize| {
+				let taken = value % n;
+				value /= n;
+				taken
+			};
+
+			let kind = take(8);
+			let rotation = Rotation::ALL[take(4)];
+			let coord = take(BLOCKS);
+			let (rise, mirror, _colorize, commented) = (take(2) == 1, take(2) == 1, take(2), take(2) == 1);
+			let lock = take(2) == 0;
+
+			let operation = (kind != 0).then(|| {
+				let mut operation = Operation {
+					kind: KINDS[kind],
+					rotation,
+					x: (coord % WIDTH) as i8,
+					y: ROWS as i8 - (coord / WIDTH) as i8 - 1,
+				};
+				let (dx, dy) = operation.offset();
+				operation.x += dx;
+				operation.y += dy;
+				operation
+			});
+
+			if commented {
+				let len = reader.read(2)?;
+				let mut escaped = String::new();
+				for _ in 0..len.div_ceil(4) {
+					let mut value = reader.read(5)?;
+					for _ in 0..4 {
+						escaped.push(COMMENT[value % 96 % COMMENT.len()] as char);
+						value /= 96;
+					}
+				}
+				escaped.truncate(len);
+				comment = unescape(&escaped);
+			}
+
+			let page = Page {
+				field: field.clone(),
+				operation,
+				lock,
+				rise,
+				mirror,
+				comment: comment.clone(),
+			};
+			field = page.next();
+			pages.push(page);
+		}
+
+		Ok(Fumen { pages })
+	}
+}
+
+// the data with the version
+impl fmt::Display for Fumen {
+	fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+		let mut writer = Writer { digits: vec!() };
+		let mut prev = vec!(0; BLOCKS);
+		let mut comment = "";
+		// where the number of pages with the same field is written
+		let mut repeat: Option<usize> = None;
+
+		for (i, page) in self.pages.iter().enumerate() {
+			let diffs: Vec<usize> = page.field
+				.iter()
+				.zip(&prev)
+				.map(|(&number, &prev)| number as usize + 8 - prev as usize)
+				.collect();
+
+			if diffs.iter().all(|&diff| diff == 8) {
+				match repeat {
+					Some(at) if writer.digits[at] < 63 => writer.digits[at] += 1,
+					_ => {
+						writer.write(8 * BLOCKS + BLOCKS - 1, 2);
+						writer.write(0, 1);
+						repeat = Some(writer.digits.len() - 1);
+					}
+				}
+			} else {
+				repeat = None;
+				for run in diffs.chunk_by(|a, b| a == b) {
+					writer.write(run[0] * BLOCKS + run.len() - 1, 2);
+				}
+			}
+
+			let (kind, rotation, coord) = match page.operation {
+				Some(operation) => {
+					let (dx, dy) = operation.offset();
+					let (x, y) = (operation.x - dx, operation.y - dy);
+					(
+						KINDS.iter().position(|&kind| kind == operation.kind).unwrap_or(0),
+						Rotation::ALL.iter().position(|&rotation| rotation == operation.rotation).unwrap_or(0),
+						((ROWS as i32 - y as i32 - 1) * WIDTH as i32 + x as i32).clamp(0, BLOCKS as i32 - 1) as usize,
+					)
+				}
+				None => (0, 0, 0),
+			};
+			let commented = page.comment != comment;
+
+			let mut value = !page.lock as usize;
+			for flag in [commented, i == 0, page.mirror, page.rise] {
+				value = value * 2 + flag as usize;
+			}
+			writer.write(((value * BLOCKS + coord) * 4 + rotation) * 8 + kind, 3);
+
+			if commented {
+				let mut escaped = escape(&page.comment);
+				escaped.truncate(COMMENT_LEN);
+				writer.write(escaped.len(), 2);
+
+				for chunk in escaped.as_bytes().chunks(4) {
+					let value = chunk
+						.iter()
+						.rev()
+						.map(|&byte| COMMENT.iter().position(|&char| char == byte).unwrap_or(0))
+						.fold(0, |value, char| value * 96 + char);
+					writer.write(value, 5);
+				}
+				comment = &page.comment;
+			}
+
+			prev = page.next();
+		}
+
+		// long data is broken by question marks
+		let data: Vec<char> = writer.digits.iter().map(|&digit| DIGITS[digit as usize] as char).collect();
+		write!(f, "{VERSION}")?;
+		for (i, digit) in data.iter().enumerate() {
+			if i >= 42 && (i - 42) % 47 == 0 {
+				write!(f, "?")?;
+			}
+			write!(f, "{digit}")?;
+		}
+		Ok(())
+	}
+}
+
+
+#[derive(Debug, Clone, PartialEq)]
+pub enum FumenError {
+	Version,
+	Character(char),
+	Truncated,
+	Field,
+	Width(usize),
+	Height(usize),
+}
+
+impl fmt::Display for FumenError {
+	fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+		match self {
+			FumenError::Version => write!(f, "only {VERSION} fumen is supported"),
+			FumenError::Character(char) => write!(f, "unexpected character {char}"),
+			FumenError::Truncated => write!(f, "the data ends too early"),
+			FumenError::Field => write!(f, "the field is broken"),
+			FumenError::Width(width) => write!(f, "board is {width} blocks wide, not {WIDTH}"),
+			FumenError::Height(height) => write!(f, "board of {height} rows doesn't fit {ROWS} rows"),
+		}
+	}
+}
+
+impl std::error::Error for FumenError {}
+
+
+// index in the field from the top row, y of the garbage row is -1
+fn index(x: usize, y: i8) -> usize {
+	(ROWS as i8 - 1 - y) as usize * WIDTH + x
+}
+
+struct Reader {
+	digits: Vec<u8>,
+	at: usize,
+}
+
+impl Reader {
+	fn is_empty(&self) -> bool {
+		self.at >= self.digits.len()
+	}
+
+	fn read(&mut self, n: usize) -> Result<usize, FumenError> {
+		let digits = self.digits.get(self.at..self.at + n).ok_or(FumenError::Truncated)?;
+		self.at += n;
+		Ok(digits.iter().rev().fold(0, |value, &digit| value * 64 + digit as usize))
+	}
+}
+
+struct Writer {
+	digits: Vec<u8>,
+}
+
+impl Writer {
+	fn write(&mut self, mut value: usize, n: usize) {
+		for _ in 0..n {
+			self.digits.push((value % 64) as u8);
+			value /= 64;
+		}
+	}
+}
+
+
+// comments are escaped the way of javascript
+fn escape(string: &str) -> String {
+	let mut escaped = String::new();
+	for char in string.chars() {
+		match char {
+			'A'..='Z' | 'a'..='z' | '0'..='9' | '@' | '*' | '_' | '+' | '-' | '.' | '/' =>
+				escaped.push(char),
+			char if (char as u32) < 0x100 => escaped.push_str(&format!("%{:02X}", char as u32)),
+			char => {
+				let mut units = [0; 2];
+				for unit in char.encode_utf16(&mut units) {
+					escaped.push_str(&format!("%u{:04X}", unit));
+				}
+			}
+		}
+	}
+	escaped
+}
+
+fn unescape(string: &str) -> String {
+	let chars: Vec<char> = string.chars().collect();
+	let mut units: Vec<u16> = vec!();
+	let mut i = 0;
+
+	while i < chars.len() {
+		let hex = |from: usize, len: usize| chars
+			.get(from..from + len)
+			.and_then(|digits| u16::from_str_radix(&digits.iter().collect::<String>(), 16).ok());
+
+		match (chars[i], chars.get(i + 1)) {
+			('%', Some('u')) if hex(i + 2, 4).is_some() => {
+				units.push(hex(i + 2, 4).unwrap());
+				i += 6;
+			}
+			('%', _) if hex(i + 1, 2).is_some() => {
+				units.push(hex(i + 1, 2).unwrap());
+				i += 3;
+			}
+			(char, _) => {
+				let mut buf = [0; 2];
+				units.extend_from_slice(char.encode_utf16(&mut buf));
+				i += 1;
+			}
+		}
+	}
+
+	String::from_utf16_lossy(&units)
+}
+
+
+#[cfg(test)]
+mod tests {
+	use super::*;
+
+	// an empty field, a field with repeated empty pages, an I placed and locked,
+	// gray blocks and a comment
+	const KNOWN: [&str; 5] = [
+		"v115@vhAAgH",
+		"v115@vhCAgHAAAAAA",
+		"v115@vhBRQJAAA",
+		"v115@9gF8DeF8DeF8DeF8NeAgH",
+		"v115@vhAAgWFAooMDEPBAAA",
+	];
+
+	fn parse(data: &str) -> Fumen {
+		data.parse().unwrap()
+	}
+
+	#[test]
+	fn known_round_trip() {
+		for data in KNOWN {
+			assert_eq!(parse(data).to_string(), data);
+		}
+	}
+
+	#[test]
+	fn repeated_empty_fields() {
+		let fumen = parse("v115@vhCAgHAAAAAA");
+		assert_eq!(fumen.pages.len(), 3);
+		assert!(fumen.pages.iter().all(|page| page.board().is_empty()));
+
+		// more pages than a single digit counts
+		let pages = vec!(fumen.pages[1].clone(); 100);
+		let data = Fumen { pages: pages.clone() }.to_string();
+		assert_eq!(parse(&data).pages, pages);
+	}
+
+	#[test]
+	fn operations() {
+		let fumen = parse("v115@vhBRQJAAA");
+		let placed = Operation { kind: 'I', rotation: Rotation::Spawn, x: 4, y: 0 };
+		assert_eq!(fumen.pages[0].operation, Some(placed));
+		assert_eq!(fumen.pages[1].operation, None);
+		assert_eq!(fumen.queue(), vec!('I'));
+
+		// the piece is locked on the next page
+		let bottom: String = (0..WIDTH).map(|x| fumen.pages[1].board().kind(x, 0)).collect();
+		assert_eq!(bottom, "...IIII...");
+	}
+
+	#[test]
+	fn gray_blocks() {
+		let board = parse("v115@9gF8DeF8DeF8DeF8NeAgH").pages[0].board();
+		assert_eq!(board.to_string(), "GGGGGG....\n".repeat(4));
+	}
+
+	#[test]
+	fn comments() {
+		let mut fumen = parse("v115@vhAAgWFAooMDEPBAAA");
+		assert_eq!(fumen.pages[0].comment, "hello");
+
+		fumen.pages[0].comment = String::from("50% \"off\" ✓");
+		assert_eq!(parse(&fumen.to_string()), fumen);
+	}
+
+	#[test]
+	fn long_data() {
+		let page = parse("v115@vhBRQJAAA").pages[0].clone();
+		let fumen = Fumen { pages: vec!(page; 30) };
+		let data = fumen.to_string();
+
+		// question marks break the data, they are skipped
+		assert!(data.contains('?'));
+		assert_eq!(parse(&data), fumen);
+		assert_eq!(parse("v115@vh?AAgH"), parse("v115@vhAAgH"));
+	}
+}
@@ -17,12 +17,11 @@ use {
 		Signed,
 		abs,
 	},
-	iced::Point,
 };
 
 
 // since it's just two num's it may represent point or vector
-#[derive(Debug, Clone, Copy)]
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash)]
 pub struct Coord<T> {
 	pub x: T,
 	pub y: T,
@@ -176,7 +175,7 @@ where T: Add<Output = T> + Sub<Output = T> + Ord + From<i8> + Copy
 	}
 
 	// provides iterating by int's on the segment
-	pub fn iter(&self) -> SegmentIter<T> {
+	pub fn iter(&self) -> SegmentIter<'_, T> {
 		SegmentIter {
 			inited: true,
 			curr: T::from(0),
@@ -332,7 +331,7 @@ where T: Add<Output = T> + Sub<Output = T> + Div<T, Output = T> +
 	}
 
 	// provides iterating by all inner and border points
-	pub fn iter(&self) -> RectangleIter<T> {
+	pub fn iter(&self) -> RectangleIter<'_, T> {
 		RectangleIter {
 			inited: true,
 			curr_y: T::from(0),
@@ -391,7 +390,7 @@ where T: Add<Output = T> + AddAssign + Sub<Output = T> +
 
 // means shifting by one unit of measurement
 // is used by tetrimino shape and shifting events
-#[derive(Debug, PartialEq, Clone, Copy)]
+#[derive(Debug, PartialEq, Eq, Hash, Clone, Copy)]
 pub enum Direction {
 	Top,
 	Rgt,
@@ -426,9 +425,9 @@ impl Direction {
 	}
 }
 
-impl Into<Coord<i8>> for Direction {
-	fn into(self) -> Coord<i8> {
-		match self {
+impl From<Direction> for Coord<i8> {
+	fn from(dir: Direction) -> Coord<i8> {
+		match dir {
 			Direction::Top => Coord { x: 0, y: 1 },
 			Direction::Rgt => Coord { x: 1, y: 0 },
 			Direction::Dwn => Coord { x: 0, y: -1 },
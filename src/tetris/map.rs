@@ -1,4 +1,4 @@
-// height of 16 blocks, width of 10 blocks
+// height of 16 blocks, width of 10 blocks by default
 //
 // 		y │			 _
 // 		  │			|+| - top, here tetriminos spawn, not visible
@@ -30,48 +30,102 @@ use {
 		},
 	},
 	super::{
-		geometry::{
-			Coord,
-			Segment,
-			Rectangle,
-			RectangleIter
-		},
+		geometry::Coord,
 		tetrimino::Tetrimino,
 	},
+	std::{
+		cmp::min,
+		fmt,
+		str::FromStr,
+		time::Duration,
+	},
 };
 
 
+// default size of the map
+pub const WIDTH: usize = 10;
+pub const HEIGHT: usize = 16;
+
+
+// letters of empty blocks and garbage in boards written as text,
+// and of blocks it's not known what pieces they are of
+pub const EMPTY: char = '.';
+pub const GARBAGE: char = 'G';
+pub const UNKNOWN: char = '?';
+
+
+// a block of the map, it's empty if its color is Non
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash)]
+pub struct Cell {
+	pub color: Color,
+	// letter of the piece the block was left by
+	pub kind: char,
+	// it was pushed up from below rather than left by a tetrimino
+	pub garbage: bool,
+	// clock of the map when the block was locked or pushed up
+	pub locked: Duration,
+}
+
+impl Cell {
+	pub const EMPTY: Cell = Cell { color: Non, kind: EMPTY, garbage: false, locked: Duration::ZERO };
+
+	pub fn block(color: Color, kind: char) -> Cell {
+		Cell { color, kind, garbage: false, locked: Duration::ZERO }
+	}
+
+	pub fn garbage(locked: Duration) -> Cell {
+		Cell { color: Grb, kind: GARBAGE, garbage: true, locked }
+	}
+
+	pub fn is_empty(&self) -> bool {
+		self.color == Non
+	}
+}
+
+
+#[derive(Clone, Hash)]
 pub struct Map {
-	// two-dim array of Color's
-	// if color is Non then block is empty else it's not
-	map: [[Color; 16]; 10],
+	// two-dim array of Cell's, first by x then by y
+	map: Vec<Vec<Cell>>,
+	width: usize,
+	height: usize,
 	// alive tetrimino that will be moved
 	drawed: Vec<Coord<usize>>,
-	// bg is selected randomly by tetrimino generator
-	bg: Color,
+	// time the map has been played for
+	clock: Duration,
 }
 
 impl Map {
-	pub fn new(bg: Color) -> Map {
+	pub fn new(width: usize, height: usize) -> Map {
 		Map {
-			map: [[Non; 16]; 10],
+			map: vec!(vec!(Cell::EMPTY; height); width),
+			width,
+			height,
 			drawed: vec!(),
-			bg,
+			clock: Duration::ZERO,
 		}
 	}
 
 	// returns coord of lowermost middle point above (not on) the map
-	pub fn top() -> Coord<i8> {
+	pub fn top(&self) -> Coord<i8> {
 		Coord {
-			x: 4,
-			y: 16,
+			x: (self.width as i8 - 1) / 2,
+			y: self.height as i8,
 		}
 	}
 
+	pub fn width(&self) -> usize {
+		self.width
+	}
+
+	pub fn height(&self) -> usize {
+		self.height
+	}
+
 	// validate tetrimino's pos and if it's valid puts it on map
 	pub fn put(&mut self, tetrimino: &Tetrimino) -> bool {
 		for tile in &self.drawed {
-			self.map[tile.x][tile.y] = Non;
+			self.map[tile.x][tile.y] = Cell::EMPTY;
 		}
 
 		// drawable tiles
@@ -80,7 +134,7 @@ impl Map {
 		for tile in tetrimino.iter() {
 			if tile.x < 0 || tile.y < 0 {
 				for tile in &self.drawed {
-			   		self.map[tile.x][tile.y] = tetrimino.color;
+			   		self.map[tile.x][tile.y] = Cell::block(tetrimino.color, tetrimino.letter());
 			   	}
 			   	return false;
 			}
@@ -89,16 +143,16 @@ impl Map {
 			let y = tile.y as usize;
 
 			// if tile is invalid
-			if x < 0 || 9 < x || y < 0 ||
-			   y < 16 && self.map[x][y] != Non {
+			if self.width <= x ||
+			   y < self.height && !self.map[x][y].is_empty() {
 			   	for tile in &self.drawed {
-			   		self.map[tile.x][tile.y] = tetrimino.color;
+			   		self.map[tile.x][tile.y] = Cell::block(tetrimino.color, tetrimino.letter());
 			   	}
 				return false
 			}
 
 			// if it's drawable
-			if y < 16 {
+			if y < self.height {
 				drawable.push(Coord::from((x, y)));
 			}
 		}
@@ -106,15 +160,34 @@ impl Map {
 		// it's reachable only if all blocks are valid
 		self.drawed.clear();
 		for tile in drawable {
-			self.map[tile.x][tile.y] = tetrimino.color;
+			self.map[tile.x][tile.y] = Cell::block(tetrimino.color, tetrimino.letter());
 			self.drawed.push(tile);
 		}
 
 		true
 	}
 
-	// leaves tetrimino as blocks in the map
+	// leaves tetrimino as blocks in the map, they are stamped with the clock
 	pub fn kill(&mut self) {
+		for tile in &self.drawed {
+			self.map[tile.x][tile.y].locked = self.clock;
+		}
+		self.drawed.clear();
+	}
+
+	pub fn advance(&mut self, dt: Duration) {
+		self.clock += dt;
+	}
+
+	pub fn clock(&self) -> Duration {
+		self.clock
+	}
+
+	// takes alive tetrimino off the map
+	pub fn remove(&mut self) {
+		for tile in &self.drawed {
+			self.map[tile.x][tile.y] = Cell::EMPTY;
+		}
 		self.drawed.clear();
 	}
 
@@ -123,7 +196,7 @@ impl Map {
 		let mut lines = 0;
 		let mut serial = 0;
 
-		for y in (0..16).rev() {
+		for y in (0..self.height).rev() {
 			if self.line_completed(y) {
 				serial += 1;
 			} else {
@@ -142,70 +215,354 @@ impl Map {
 	fn burn_from(&mut self, line: usize, serial: usize) {
 		if serial == 0 { return }
 
-		for y in (line + serial)..16 {
-			for x in 0..10 {
+		for y in (line + serial)..self.height {
+			for x in 0..self.width {
 				self.map[x][y - serial] = self.map[x][y];
 			}
 		}
 
-		for y in (16 - serial)..16 {
-			for x in 0..10 {
-				self.map[x][y] = Non;
+		for y in (self.height - serial)..self.height {
+			for x in 0..self.width {
+				self.map[x][y] = Cell::EMPTY;
+			}
+		}
+	}
+
+	// pushes the stack up by rows of garbage with holes in hole_columns
+	// the alive tetrimino is wiped out and has to be put again
+	// returns false if some blocks were pushed over the top
+	pub fn add_garbage(&mut self, rows: usize, hole_columns: &[usize]) -> bool {
+		for tile in &self.drawed {
+			self.map[tile.x][tile.y] = Cell::EMPTY;
+		}
+		self.drawed.clear();
+
+		let rows = min(rows, self.height);
+		let mut survived = true;
+
+		for y in (self.height - rows)..self.height {
+			if !self.line_empty(y) {
+				survived = false;
 			}
 		}
+
+		for y in (rows..self.height).rev() {
+			for x in 0..self.width {
+				self.map[x][y] = self.map[x][y - rows];
+			}
+		}
+
+		for y in 0..rows {
+			for x in 0..self.width {
+				self.map[x][y] =
+					if hole_columns.contains(&x) { Cell::EMPTY } else { Cell::garbage(self.clock) };
+			}
+		}
+
+		survived
+	}
+
+	fn line_empty(&self, y: usize) -> bool {
+		for x in 0..self.width {
+			if !self.map[x][y].is_empty() {
+				return false;
+			}
+		}
+		true
 	}
 
 	fn line_completed(&self, y: usize) -> bool {
-		for x in 0..9 {
-			if self.map[x][y] == Non {
+		for x in 0..self.width {
+			if self.map[x][y].is_empty() {
 				return false;
 			}
 		}
 		true
 	}
 
-	pub fn iter(&self) -> MapIter {
+	// gives the color to every block of the kind
+	pub fn paint(&mut self, kind: char, color: Color) {
+		for cell in self.map.iter_mut().flatten() {
+			if cell.kind == kind && !cell.garbage {
+				cell.color = color;
+			}
+		}
+	}
+
+	// replaces blocks with ones of the board lying on the bottom,
+	// the alive tetrimino is wiped out and has to be put again
+	pub fn load(&mut self, board: &Map) -> Result<(), BoardError> {
+		if board.width != self.width || board.height > self.height {
+			return Err(BoardError::Size(board.width, board.height));
+		}
+
+		self.drawed.clear();
+		for x in 0..self.width {
+			for y in 0..self.height {
+				self.map[x][y] = match y < board.height {
+					true => Cell { locked: self.clock, ..board.map[x][y] },
+					false => Cell::EMPTY,
+				};
+			}
+		}
+		Ok(())
+	}
+
+	pub fn is_empty(&self) -> bool {
+		(0..self.height).all(|y| self.line_empty(y))
+	}
+
+	// walls and the floor block the way as well as filled blocks,
+	// there is nothing above the map
+	pub fn blocked(&self, x: i8, y: i8) -> bool {
+		if x < 0 || y < 0 || x as usize >= self.width {
+			return true;
+		}
+		(y as usize) < self.height && self.filled(x as usize, y as usize)
+	}
+
+	pub fn filled(&self, x: usize, y: usize) -> bool {
+		!self.map[x][y].is_empty()
+	}
+
+	// letter of the block as it's written in boards
+	pub fn kind(&self, x: usize, y: usize) -> char {
+		self.map[x][y].kind
+	}
+
+	// puts the block written with the letter, blocks of pieces are gray
+	// until they are painted
+	pub fn set_kind(&mut self, x: usize, y: usize, kind: char) {
+		self.map[x][y] = match kind {
+			EMPTY => Cell::EMPTY,
+			GARBAGE => Cell::garbage(Duration::ZERO),
+			kind => Cell::block(Grb, kind),
+		};
+	}
+
+	pub fn is_garbage(&self, x: usize, y: usize) -> bool {
+		self.map[x][y].garbage
+	}
+
+	// number of rows with some garbage left in them
+	pub fn garbage_rows(&self) -> usize {
+		(0..self.height)
+			.filter(|&y| (0..self.width).any(|x| self.is_garbage(x, y)))
+			.count()
+	}
+
+	// whether the block belongs to alive tetrimino
+	pub fn alive(&self, x: usize, y: usize) -> bool {
+		self.drawed.contains(&Coord { x, y })
+	}
+
+	// number of rows up to the topmost filled block in the column
+	pub fn column_height(&self, x: usize) -> usize {
+		(0..self.height).rev().find(|y| self.filled(x, *y)).map_or(0, |y| y + 1)
+	}
+
+	pub fn iter(&self) -> MapIter<'_> {
 		MapIter {
 			curr: Coord { x: 0, y: 0 },
-			map: &self.map,
-			bg: self.bg,
+			map: self,
+		}
+	}
+}
+
+
+// rows from the top one to the bottom one, one letter per block
+impl fmt::Display for Map {
+	fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+		for y in (0..self.height).rev() {
+			let row: String = (0..self.width).map(|x| self.map[x][y].kind).collect();
+			writeln!(f, "{row}")?;
+		}
+		Ok(())
+	}
+}
+
+// the same rows, blank lines and indentation are skipped,
+// blocks of pieces are gray until they are painted
+impl FromStr for Map {
+	type Err = BoardError;
+
+	fn from_str(string: &str) -> Result<Map, BoardError> {
+		let rows: Vec<&str> = string
+			.lines()
+			.map(str::trim)
+			.filter(|row| !row.is_empty())
+			.collect();
+
+		let width = rows.first().ok_or(BoardError::Empty)?.chars().count();
+		let mut map = Map::new(width, rows.len());
+
+		// the last row is the bottom one
+		for (y, row) in rows.iter().rev().enumerate() {
+			if row.chars().count() != width {
+				return Err(BoardError::Ragged(row.to_string()));
+			}
+
+			for (x, kind) in row.chars().enumerate() {
+				map.set_kind(x, y, kind);
+			}
 		}
+
+		Ok(map)
+	}
+}
+
+
+#[derive(Debug, Clone, PartialEq)]
+pub enum BoardError {
+	Empty,
+	Ragged(String),
+	// width and height of the board that doesn't fit
+	Size(usize, usize),
+}
+
+impl fmt::Display for BoardError {
+	fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+		match self {
+			BoardError::Empty => write!(f, "there are no rows"),
+			BoardError::Ragged(row) => write!(f, "row {row} is of another width"),
+			BoardError::Size(width, height) =>
+				write!(f, "board {width}x{height} doesn't fit the map"),
+		}
+	}
+}
+
+impl std::error::Error for BoardError {}
+
+
+// a block of the map as it's shown
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub struct Tile {
+	// none if it's empty
+	pub color: Color,
+	// letter of the piece the block belongs to, themes may color pieces by it
+	pub kind: char,
+	// when the block was locked, there is no time for empty blocks
+	// and blocks of the alive tetrimino
+	pub locked: Option<Duration>,
+}
+
+// the kind of blocks only known by their colors, like of remote boards,
+// isn't known unless they are empty or garbage
+impl From<Color> for Tile {
+	fn from(color: Color) -> Tile {
+		let kind = match color {
+			Non => EMPTY,
+			Grb => GARBAGE,
+			_ => UNKNOWN,
+		};
+		Tile { color, kind, locked: None }
 	}
 }
 
 
 // iterating first by x then by y
-// returns colors of blocks on the map
+// returns tiles of blocks on the map
 pub struct MapIter<'a> {
 	curr: Coord<usize>,
-	map: &'a [[Color; 16]; 10],
-	bg: Color,
+	map: &'a Map,
 }
 
 impl Iterator for MapIter<'_> {
-	type Item = (Coord<usize>, Color);
+	type Item = (Coord<usize>, Tile);
 
 	fn next(&mut self) -> Option<Self::Item> {
-		if self.curr.y > 15 {
+		if self.map.width == 0 || self.curr.y >= self.map.height {
 		   	return None
 		}
 
 		let coord = self.curr;
+		let cell = self.map.map[coord.x][coord.y];
 
-		let color = 
-			if self.map[coord.x][coord.y] == Non {
-				self.bg
+		let tile =
+			if cell.is_empty() {
+				Tile::from(Non)
+			} else if self.map.alive(coord.x, coord.y) {
+				Tile { color: cell.color, kind: cell.kind, locked: None }
 			} else {
-				self.map[coord.x][coord.y]
+				Tile { color: cell.color, kind: cell.kind, locked: Some(cell.locked) }
 			};
 
-		if coord.x == 9 {
+		if coord.x == self.map.width - 1 {
 			self.curr.y += 1;
 			self.curr.x = 0;
 		} else {
 			self.curr.x += 1;
 		}
 
-		Some((coord, color))
+		Some((coord, tile))
+	}
+}
+
+
+#[cfg(test)]
+mod tests {
+	use super::*;
+
+	const BOARD: &str = "\
+		....\n\
+		.T..\n\
+		TTTG\n\
+		GGG.\n";
+
+	fn parse(board: &str) -> Map {
+		board.parse().unwrap()
+	}
+
+	#[test]
+	fn round_trip() {
+		let map = parse(BOARD);
+		assert_eq!((map.width(), map.height()), (4, 4));
+		assert_eq!(map.to_string(), BOARD);
+		assert_eq!(parse(&map.to_string()).to_string(), BOARD);
+
+		// the last row is the bottom one
+		assert_eq!(map.kind(1, 2), 'T');
+		assert_eq!(map.kind(3, 0), EMPTY);
+		assert!(!map.filled(0, 3));
+	}
+
+	#[test]
+	fn blank_and_indented_lines() {
+		let map = parse("\n    .T..\n\n\tTTT.  \n\n");
+		assert_eq!((map.width(), map.height()), (4, 2));
+		assert_eq!(map.to_string(), ".T..\nTTT.\n");
+	}
+
+	#[test]
+	fn errors() {
+		assert_eq!("".parse::<Map>().err(), Some(BoardError::Empty));
+		assert_eq!(" \n\t\n".parse::<Map>().err(), Some(BoardError::Empty));
+		assert_eq!("....\n...\n".parse::<Map>().err(), Some(BoardError::Ragged(String::from("..."))));
+	}
+
+	#[test]
+	fn garbage() {
+		let map = parse(BOARD);
+		assert!(map.is_garbage(0, 0));
+		assert!(map.is_garbage(3, 1));
+		assert!(!map.is_garbage(0, 1));
+		assert!(!map.is_garbage(3, 0));
+		assert_eq!(map.garbage_rows(), 2);
+	}
+
+	#[test]
+	fn load() {
+		let mut map = Map::new(WIDTH, HEIGHT);
+		let board = parse("I.........\nIGGGGGGGG.\n");
+		assert_eq!(map.load(&board), Ok(()));
+		assert_eq!(map.kind(0, 1), 'I');
+		assert!(map.is_garbage(1, 0));
+		assert!(!map.filled(0, 2));
+		assert_eq!(map.garbage_rows(), 1);
+
+		let narrow = Map::new(WIDTH - 1, 2);
+		assert_eq!(map.load(&narrow), Err(BoardError::Size(WIDTH - 1, 2)));
+		let tall = Map::new(WIDTH, HEIGHT + 1);
+		assert_eq!(map.load(&tall), Err(BoardError::Size(WIDTH, HEIGHT + 1)));
 	}
 }
@@ -0,0 +1,157 @@
+use {
+	crate::color::Color,
+	super::geometry::Coord,
+};
+
+
+// a kind of tetrimino, all tetriminos of the kind share it
+#[derive(Debug, Clone, PartialEq)]
+pub struct Piece {
+	pub name: String,
+	// blocks relatively to the center in each rotation state,
+	// the next state is the previous one rotated clockwise
+	pub states: Vec<Vec<Coord<i8>>>,
+	// where the center spawns relatively to the top of the map
+	pub spawn: Coord<i8>,
+	// given by the serial number if it's not set, then pieces after
+	// the seventh one share colors with the first ones
+	pub color: Option<Color>,
+}
+
+impl Piece {
+	// rotation states are made by turning blocks around the center,
+	// it spawns with the lowermost blocks right above the map
+	pub fn new(name: &str, blocks: &[(i8, i8)]) -> Piece {
+		let mut states = vec!(blocks.iter().map(|&block| Coord::from(block)).collect::<Vec<_>>());
+
+		for i in 0..3 {
+			let turned = states[i]
+				.iter()
+				.map(|block| Coord { x: block.y, y: -block.x })
+				.collect();
+			states.push(turned);
+		}
+
+		Piece::with_states(name, states)
+	}
+
+	// the same but rotation states are given as they are
+	pub fn with_states(name: &str, states: Vec<Vec<Coord<i8>>>) -> Piece {
+		let lowest = states
+			.first()
+			.and_then(|blocks| blocks.iter().map(|block| block.y).min())
+			.unwrap_or(0);
+
+		Piece {
+			name: String::from(name),
+			states,
+			spawn: Coord { x: 0, y: -lowest },
+			color: None,
+		}
+	}
+
+	pub fn size(&self) -> usize {
+		self.states.first().map_or(0, Vec::len)
+	}
+
+	// blocks of the piece are written with it in boards,
+	// mirrored pieces like N' are lowercase
+	pub fn letter(&self) -> char {
+		let letter = self.name.chars().next().unwrap_or('?');
+		match self.name.ends_with('\'') {
+			true => letter.to_ascii_lowercase(),
+			false => letter.to_ascii_uppercase(),
+		}
+	}
+}
+
+
+// built in set of pieces by its name
+pub fn piece_set(name: &str) -> Option<Vec<Piece>> {
+	match name {
+		"tetromino" => Some(tetrominoes()),
+		"pentomino" => Some(pentominoes()),
+		"tromino" => Some(trominoes()),
+		_ => None,
+	}
+}
+
+
+// the center is |+| in following schemes
+pub fn tetrominoes() -> Vec<Piece> {
+	vec!(
+		//	 _
+		//	|+|
+		//	|‾|
+		//	|‾|
+		//	|‾|
+		//	 ‾
+		Piece::new("I", &[(0, 0), (0, -1), (0, -2), (0, -3)]),
+		//	   _
+		//	  |+|
+		//	  |‾|
+		//	|‾|‾|
+		//	 ‾ ‾
+		Piece::new("J", &[(0, 0), (0, -1), (0, -2), (-1, -2)]),
+		//	 _
+		//	|+|
+		//	|‾|
+		//	|‾|‾|
+		//	 ‾ ‾
+		Piece::new("L", &[(0, 0), (0, -1), (0, -2), (1, -2)]),
+		//	 _ _
+		//	|+| |
+		//	|‾|‾|
+		//	 ‾ ‾
+		Piece::new("O", &[(0, 0), (1, 0), (1, -1), (0, -1)]),
+		//	   _ _
+		//	 _|_|_|
+		//	|+| |
+		//	 ‾ ‾
+		Piece::new("S", &[(0, 0), (1, 0), (1, 1), (2, 1)]),
+		//	 _ _
+		//	|+| |
+		//	 ‾|‾|‾|
+		//	   ‾ ‾
+		Piece::new("Z", &[(0, 0), (1, 0), (1, -1), (2, -1)]),
+		//	   _
+		//	 _|_|_
+		//	| |+| |
+		//	 ‾ ‾ ‾
+		Piece::new("T", &[(0, 0), (-1, 0), (0, 1), (1, 0)]),
+	)
+}
+
+// one-sided pentominoes, mirrored ones are separate pieces,
+// there are only seven colors for the eighteen of them so some share colors
+// (I, Y, U are red, J, Y', V are green and so on), themes tell them apart
+// by their letters
+pub fn pentominoes() -> Vec<Piece> {
+	vec!(
+		Piece::new("I", &[(0, 0), (0, -1), (0, -2), (0, -3), (0, -4)]),
+		Piece::new("J", &[(0, 0), (0, -1), (0, -2), (0, -3), (-1, -3)]),
+		Piece::new("L", &[(0, 0), (0, -1), (0, -2), (0, -3), (1, -3)]),
+		Piece::new("P", &[(0, 0), (1, 0), (0, -1), (1, -1), (0, -2)]),
+		Piece::new("Q", &[(0, 0), (-1, 0), (0, -1), (-1, -1), (0, -2)]),
+		Piece::new("N", &[(0, 0), (0, -1), (-1, -1), (-1, -2), (-1, -3)]),
+		Piece::new("N'", &[(0, 0), (0, -1), (1, -1), (1, -2), (1, -3)]),
+		Piece::new("Y", &[(0, 0), (0, 1), (0, -1), (0, -2), (1, 0)]),
+		Piece::new("Y'", &[(0, 0), (0, 1), (0, -1), (0, -2), (-1, 0)]),
+		Piece::new("F", &[(0, 0), (0, 1), (1, 1), (-1, 0), (0, -1)]),
+		Piece::new("F'", &[(0, 0), (0, 1), (-1, 1), (1, 0), (0, -1)]),
+		Piece::new("S", &[(0, 0), (0, 1), (1, 1), (0, -1), (-1, -1)]),
+		Piece::new("Z", &[(0, 0), (0, 1), (-1, 1), (0, -1), (1, -1)]),
+		Piece::new("T", &[(0, 0), (-1, 0), (1, 0), (0, -1), (0, -2)]),
+		Piece::new("U", &[(0, 0), (-1, 0), (1, 0), (-1, 1), (1, 1)]),
+		Piece::new("V", &[(0, 0), (1, 0), (2, 0), (0, 1), (0, 2)]),
+		Piece::new("W", &[(0, 0), (0, 1), (1, 0), (1, -1), (2, -1)]),
+		Piece::new("X", &[(0, 0), (1, 0), (-1, 0), (0, 1), (0, -1)]),
+	)
+}
+
+pub fn trominoes() -> Vec<Piece> {
+	vec!(
+		Piece::new("I", &[(0, 0), (0, -1), (0, -2)]),
+		Piece::new("L", &[(0, 0), (0, -1), (1, -1)]),
+	)
+}
@@ -1,42 +1,44 @@
-use {
-	crate::{
-		color::{
-			Color::{
-				self,
-				*,
-			},
-			N_COLORS,
-		},
-	},
-	rand::{
-		prelude::*,
-		random,
-		thread_rng,
-	},
+use rand::{
+	prelude::*,
+	rngs::StdRng,
 };
 
 
+// how serial numbers of tetriminos are picked
+#[derive(Debug, Clone, PartialEq)]
+pub enum Randomizer {
+	// any but the previous one
+	Classic,
+	// all of them in random order, then again
+	Bag,
+	// exactly these ones in this order, then no more
+	Fixed(Vec<u8>),
+}
+
+
 // generate random u8 different from the previous one
-pub fn non_serial_rnd(module: u8) -> u8 {
-	static mut PREV: u8 = 0;
-	let mut rnd = random::<u8>() % module;
-
-	unsafe {	// danger zone
-		while rnd == PREV {
-			rnd = random::<u8>() % module;
-		}
-		PREV = rnd;
+pub fn non_serial_rnd(rng: &mut StdRng, prev: &mut u8, module: u8) -> u8 {
+	// a single one can't be different
+	if module < 2 {
+		*prev = 0;
+		return 0;
+	}
+
+	let mut rnd = rng.gen::<u8>() % module;
+
+	while rnd == *prev {
+		rnd = rng.gen::<u8>() % module;
 	}
+	*prev = rnd;
 
 	rnd
 }
 
 
-// build sequence of all possible colors
-pub fn shuffle_colors() -> [Color; N_COLORS] {
-	let mut rng = thread_rng();
-	let mut colors = Color::all();
-	colors.shuffle(&mut rng);
+// all numbers less than module in random order
+pub fn shuffled_bag(rng: &mut StdRng, module: u8) -> Vec<u8> {
+	let mut bag: Vec<u8> = (0..module).collect();
+	bag.shuffle(rng);
 
-	colors
+	bag
 }
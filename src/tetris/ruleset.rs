@@ -0,0 +1,506 @@
+// rules of the game loaded from a toml file, for example
+//
+//	width = 10
+//	height = 20
+//	randomizer = "bag"			# classic or bag
+//	set = "tetromino"			# built in pieces the names below refer to
+//	pieces = ["I", "O", "T", "plus"]
+//
+//	[[piece]]
+//	name = "plus"
+//	blocks = [[0, 0], [1, 0], [-1, 0], [0, 1], [0, -1]]
+//	color = "pink"				# by the order of pieces if it's not set
+//	spawn = [0, 1]				# above the top of the map by default
+//	states = [...]				# rotation table instead of blocks
+//
+// if pieces aren't listed then all defined ones are taken,
+// if there are no defined ones then the whole set is taken,
+// pieces are written in boards by the first letters of their names
+// so these should differ and not be G, mirrored pieces like N' are lowercase
+
+use {
+	super::{
+		geometry::Coord,
+		map::{
+			EMPTY,
+			GARBAGE,
+			HEIGHT,
+			UNKNOWN,
+			WIDTH,
+		},
+		piece::{
+			piece_set,
+			Piece,
+		},
+		random::Randomizer,
+		Setup,
+	},
+	serde::Deserialize,
+	std::{
+		collections::HashSet,
+		fmt,
+		fs,
+		io,
+		path::Path,
+		str::FromStr,
+	},
+};
+
+
+// the map should have room for tetriminos and fit i8 coords, blocks are as high
+// as the map, the spawn and the block above the center together, and rotations
+// and garbage may lift them by twice the offset more
+const MIN_SIZE: usize = 4;
+const MAX_SIZE: usize = 64;
+// the farthest a block or the spawn may be from the center
+const MAX_OFFSET: i8 = 8;
+
+
+#[derive(Debug, Clone)]
+pub struct Ruleset {
+	pub width: usize,
+	pub height: usize,
+	pub randomizer: Randomizer,
+	pub pieces: Vec<Piece>,
+}
+
+impl Ruleset {
+	pub fn load(path: &Path) -> Result<Ruleset, RulesetError> {
+		fs::read_to_string(path)
+			.map_err(RulesetError::Io)?
+			.parse()
+	}
+
+	pub fn setup(&self, seed: u64) -> Setup {
+		Setup {
+			seed,
+			width: self.width,
+			height: self.height,
+			randomizer: self.randomizer.clone(),
+			pieces: self.pieces.clone(),
+		}
+	}
+}
+
+impl FromStr for Ruleset {
+	type Err = RulesetError;
+
+	fn from_str(string: &str) -> Result<Ruleset, RulesetError> {
+		let file: File = toml::from_str(string)
+			.map_err(|err| RulesetError::Syntax(err.message().to_string()))?;
+
+		if !(MIN_SIZE..=MAX_SIZE).contains(&file.width) ||
+		   !(MIN_SIZE..=MAX_SIZE).contains(&file.height) {
+			return Err(RulesetError::Size(file.width, file.height));
+		}
+
+		let randomizer = match file.randomizer.as_str() {
+			"classic" => Randomizer::Classic,
+			"bag" => Randomizer::Bag,
+			other => return Err(RulesetError::UnknownRandomizer(other.to_string())),
+		};
+
+		let set = piece_set(&file.set)
+			.ok_or_else(|| RulesetError::UnknownSet(file.set.clone()))?;
+
+		let mut defined = vec!();
+		for definition in file.piece {
+			let piece = definition.piece()?;
+			if defined.iter().any(|other: &Piece| other.name == piece.name) {
+				return Err(RulesetError::Duplicate(piece.name));
+			}
+			defined.push(piece);
+		}
+
+		// defined pieces take precedence over the set ones with the same name
+		let pieces = match file.pieces {
+			Some(names) => names
+				.into_iter()
+				.map(|name| defined
+					.iter()
+					.chain(&set)
+					.find(|piece| piece.name == name)
+					.cloned()
+					.ok_or(RulesetError::UnknownPiece(name)))
+				.collect::<Result<Vec<_>, _>>()?,
+			None if defined.is_empty() => set,
+			None => defined,
+		};
+
+		if pieces.is_empty() || pieces.len() > u8::MAX as usize {
+			return Err(RulesetError::PieceCount(pieces.len()));
+		}
+
+		for piece in &pieces {
+			fits(piece, file.width, file.height)?;
+		}
+
+		// boards are written with letters of pieces
+		for (i, piece) in pieces.iter().enumerate() {
+			let letter = piece.letter();
+			if [EMPTY, GARBAGE, UNKNOWN].contains(&letter) || letter.is_whitespace() {
+				return Err(RulesetError::ReservedLetter(piece.name.clone(), letter));
+			}
+			if let Some(other) = pieces[..i].iter().find(|other| other.letter() == letter) {
+				return Err(RulesetError::SameLetter(other.name.clone(), piece.name.clone(), letter));
+			}
+		}
+
+		Ok(Ruleset {
+			width: file.width,
+			height: file.height,
+			randomizer,
+			pieces,
+		})
+	}
+}
+
+// the spawned tetrimino should be within the walls and above the floor,
+// otherwise it would never be put on the map
+fn fits(piece: &Piece, width: usize, height: usize) -> Result<(), RulesetError> {
+	let center = Coord {
+		x: (width as i8 - 1) / 2 + piece.spawn.x,
+		y: height as i8 + piece.spawn.y,
+	};
+
+	for block in &piece.states[0] {
+		if !(0..width as i8).contains(&(center.x + block.x)) || center.y + block.y < 0 {
+			return Err(RulesetError::OutOfMap(piece.name.clone()));
+		}
+	}
+
+	Ok(())
+}
+
+
+#[derive(Debug)]
+pub enum RulesetError {
+	Io(io::Error),
+	Syntax(String),
+	Size(usize, usize),
+	UnknownRandomizer(String),
+	UnknownSet(String),
+	UnknownPiece(String),
+	UnknownColor(String, String),
+	Duplicate(String),
+	// the piece has no blocks, its states differ in size
+	// or they are too far from the center
+	Malformed(String),
+	// the piece, its rotation state and the block
+	Overlapping(String, usize, (i8, i8)),
+	OutOfMap(String),
+	PieceCount(usize),
+	// the piece and its letter
+	ReservedLetter(String, char),
+	// both pieces and the letter they share
+	SameLetter(String, String, char),
+}
+
+impl fmt::Display for RulesetError {
+	fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+		match self {
+			RulesetError::Io(err) => write!(f, "unable to read the ruleset: {err}"),
+			RulesetError::Syntax(msg) => write!(f, "invalid ruleset: {msg}"),
+			RulesetError::Size(width, height) =>
+				write!(f, "map of {width}x{height} should be from {MIN_SIZE} to {MAX_SIZE} blocks in each direction"),
+			RulesetError::UnknownRandomizer(name) =>
+				write!(f, "unknown randomizer {name}, it's classic or bag"),
+			RulesetError::UnknownSet(name) =>
+				write!(f, "unknown set {name}, it's tetromino, pentomino or tromino"),
+			RulesetError::UnknownPiece(name) =>
+				write!(f, "piece {name} is neither defined nor in the set"),
+			RulesetError::UnknownColor(name, color) =>
+				write!(f, "piece {name} has unknown color {color}"),
+			RulesetError::Duplicate(name) => write!(f, "piece {name} is defined twice"),
+			RulesetError::Malformed(name) =>
+				write!(f, "piece {name} should have the same number of blocks in each state, \
+						   not farther than {MAX_OFFSET} from the center"),
+			RulesetError::Overlapping(name, state, (x, y)) =>
+				write!(f, "piece {name} has overlapping blocks at [{x}, {y}] in state {state}"),
+			RulesetError::OutOfMap(name) => write!(f, "piece {name} doesn't fit the map"),
+			RulesetError::PieceCount(count) =>
+				write!(f, "there should be from 1 to 255 pieces, not {count}"),
+			RulesetError::ReservedLetter(name, letter) =>
+				write!(f, "piece {name} would be written with {letter:?} that is kept for other blocks"),
+			RulesetError::SameLetter(name, other, letter) =>
+				write!(f, "pieces {name} and {other} would both be written with {letter:?}, \
+						   their names should start differently"),
+		}
+	}
+}
+
+impl std::error::Error for RulesetError {}
+
+
+// the file as it's written
+#[derive(Deserialize)]
+#[serde(deny_unknown_fields)]
+struct File {
+	#[serde(default = "default_width")]
+	width: usize,
+	#[serde(default = "default_height")]
+	height: usize,
+	#[serde(default = "default_randomizer")]
+	randomizer: String,
+	#[serde(default = "default_set")]
+	set: String,
+	pieces: Option<Vec<String>>,
+	#[serde(default)]
+	piece: Vec<Definition>,
+}
+
+fn default_width() -> usize { WIDTH }
+fn default_height() -> usize { HEIGHT }
+fn default_randomizer() -> String { String::from("classic") }
+fn default_set() -> String { String::from("tetromino") }
+
+#[derive(Deserialize)]
+#[serde(deny_unknown_fields)]
+struct Definition {
+	name: String,
+	blocks: Option<Vec<(i8, i8)>>,
+	states: Option<Vec<Vec<(i8, i8)>>>,
+	spawn: Option<(i8, i8)>,
+	color: Option<String>,
+}
+
+impl Definition {
+	fn piece(self) -> Result<Piece, RulesetError> {
+		let mut piece = match (&self.blocks, self.states) {
+			(Some(blocks), None) => Piece::new(&self.name, blocks),
+			(None, Some(states)) => {
+				let states = states
+					.into_iter()
+					.map(|blocks| blocks.into_iter().map(Coord::from).collect())
+					.collect();
+				Piece::with_states(&self.name, states)
+			}
+			_ => return Err(RulesetError::Malformed(self.name)),
+		};
+
+		let size = piece.size();
+		let far = |coord: &Coord<i8>| coord.x.abs() > MAX_OFFSET || coord.y.abs() > MAX_OFFSET;
+		let spawn = self.spawn.map_or(piece.spawn, Coord::from);
+
+		if size == 0 || far(&spawn) ||
+		   piece.states.iter().any(|blocks| blocks.len() != size || blocks.iter().any(far)) {
+			return Err(RulesetError::Malformed(self.name));
+		}
+
+		for (state, blocks) in piece.states.iter().enumerate() {
+			let mut seen = HashSet::new();
+			for block in blocks {
+				if !seen.insert(block) {
+					return Err(RulesetError::Overlapping(self.name, state, (block.x, block.y)));
+				}
+			}
+		}
+
+		piece.spawn = spawn;
+
+		if let Some(color) = self.color {
+			piece.color = Some(color
+				.parse()
+				.map_err(|_| RulesetError::UnknownColor(self.name.clone(), color))?);
+		}
+
+		Ok(piece)
+	}
+}
+
+
+#[cfg(test)]
+mod tests {
+	use {
+		super::*,
+		crate::{
+			game::Msg,
+			tetris::Tetris,
+		},
+	};
+
+	fn parse(ruleset: &str) -> Result<Ruleset, RulesetError> {
+		ruleset.parse()
+	}
+
+	#[test]
+	fn mirrored_letters() {
+		let ruleset = parse("set = \"pentomino\"").unwrap();
+		let letters: HashSet<char> = ruleset.pieces.iter().map(Piece::letter).collect();
+		assert_eq!(letters.len(), ruleset.pieces.len());
+		assert!(letters.contains(&'N') && letters.contains(&'n'));
+	}
+
+	#[test]
+	fn same_letter() {
+		let err = parse("
+			set = \"pentomino\"
+			pieces = [\"N\", \"N'\", \"nub\"]
+
+			[[piece]]
+			name = \"nub\"
+			blocks = [[0, 0], [1, 0]]
+		").unwrap_err();
+		assert!(matches!(err, RulesetError::SameLetter(name, other, 'N') if name == "N" && other == "nub"));
+
+		// mirrored ones are told by the case
+		let err = parse("
+			set = \"pentomino\"
+			pieces = [\"N'\", \"n'\"]
+
+			[[piece]]
+			name = \"n'\"
+			blocks = [[0, 0], [1, 0]]
+		").unwrap_err();
+		assert!(matches!(err, RulesetError::SameLetter(_, _, 'n')));
+	}
+
+	#[test]
+	fn reserved_letter() {
+		for (name, letter) in [("garbage", GARBAGE), (".", EMPTY), ("?", UNKNOWN), (" x", ' ')] {
+			let err = parse(&format!("
+				[[piece]]
+				name = {name:?}
+				blocks = [[0, 0], [1, 0]]
+			")).unwrap_err();
+			assert!(matches!(err, RulesetError::ReservedLetter(_, found) if found == letter), "{name}");
+		}
+	}
+
+	#[test]
+	fn shipped() {
+		let ruleset = Ruleset::load("rulesets/plus.toml".as_ref()).unwrap();
+		assert_eq!((ruleset.width, ruleset.height), (10, 20));
+		assert_eq!(ruleset.pieces.len(), 8);
+		assert_eq!(ruleset.pieces[7].name, "plus");
+	}
+
+	#[test]
+	fn io_and_syntax() {
+		assert!(matches!(Ruleset::load("rulesets/missing.toml".as_ref()), Err(RulesetError::Io(_))));
+		assert!(matches!(parse("width = "), Err(RulesetError::Syntax(_))));
+		assert!(matches!(parse("speed = 1"), Err(RulesetError::Syntax(_))));
+	}
+
+	#[test]
+	fn size() {
+		assert!(matches!(parse("width = 3"), Err(RulesetError::Size(3, HEIGHT))));
+		assert!(matches!(parse("height = 65"), Err(RulesetError::Size(WIDTH, 65))));
+		assert!(parse("width = 64\nheight = 64").is_ok());
+	}
+
+	#[test]
+	fn unknown_names() {
+		assert!(matches!(parse("randomizer = \"random\""), Err(RulesetError::UnknownRandomizer(name)) if name == "random"));
+		assert!(matches!(parse("set = \"hexomino\""), Err(RulesetError::UnknownSet(name)) if name == "hexomino"));
+		assert!(matches!(parse("pieces = [\"I\", \"plus\"]"), Err(RulesetError::UnknownPiece(name)) if name == "plus"));
+
+		let err = parse("
+			[[piece]]
+			name = \"plus\"
+			blocks = [[0, 0], [1, 0]]
+			color = \"mauve\"
+		").unwrap_err();
+		assert!(matches!(err, RulesetError::UnknownColor(name, color) if name == "plus" && color == "mauve"));
+	}
+
+	#[test]
+	fn duplicate() {
+		let err = parse("
+			[[piece]]
+			name = \"plus\"
+			blocks = [[0, 0], [1, 0]]
+
+			[[piece]]
+			name = \"plus\"
+			blocks = [[0, 0], [0, 1]]
+		").unwrap_err();
+		assert!(matches!(err, RulesetError::Duplicate(name) if name == "plus"));
+	}
+
+	#[test]
+	fn malformed() {
+		let pieces = [
+			// no blocks
+			"blocks = []",
+			// both blocks and states
+			"blocks = [[0, 0]]\nstates = [[[0, 0]]]",
+			// states of different sizes
+			"states = [[[0, 0], [1, 0]], [[0, 0]]]",
+			// farther than the offset
+			"blocks = [[0, 0], [9, 0]]",
+			"blocks = [[0, 0], [0, -9]]",
+			"blocks = [[0, 0]]\nspawn = [0, 9]",
+		];
+		for piece in pieces {
+			let err = parse(&format!("width = 64\n[[piece]]\nname = \"far\"\n{piece}")).unwrap_err();
+			assert!(matches!(err, RulesetError::Malformed(_)), "{piece}");
+		}
+
+		// the offset itself is allowed
+		assert!(parse("width = 64\n[[piece]]\nname = \"far\"\nblocks = [[0, 0], [8, 0], [-8, -8]]\nspawn = [0, 8]").is_ok());
+	}
+
+	#[test]
+	fn overlapping() {
+		let err = parse("
+			[[piece]]
+			name = \"plus\"
+			states = [[[0, 0], [1, 0]], [[0, 0], [0, 0]]]
+		").unwrap_err();
+		assert!(matches!(err, RulesetError::Overlapping(name, 1, (0, 0)) if name == "plus"));
+	}
+
+	#[test]
+	fn out_of_map() {
+		// through the wall
+		let err = parse("
+			width = 4
+			[[piece]]
+			name = \"long\"
+			blocks = [[0, 0], [1, 0], [2, 0], [3, 0]]
+		").unwrap_err();
+		assert!(matches!(err, RulesetError::OutOfMap(name) if name == "long"));
+
+		// below the floor
+		let err = parse("
+			height = 4
+			[[piece]]
+			name = \"deep\"
+			blocks = [[0, 0]]
+			spawn = [0, -5]
+		").unwrap_err();
+		assert!(matches!(err, RulesetError::OutOfMap(name) if name == "deep"));
+	}
+
+	#[test]
+	fn piece_count() {
+		assert!(matches!(parse("pieces = []"), Err(RulesetError::PieceCount(0))));
+	}
+
+	// the largest map and the farthest blocks stay within i8 coords
+	#[test]
+	fn limits_are_playable() {
+		let ruleset = parse("
+			width = 64
+			height = 64
+			randomizer = \"bag\"
+			[[piece]]
+			name = \"far\"
+			blocks = [[8, 8], [-8, -8], [8, -8], [-8, 8]]
+			spawn = [0, 8]
+		").unwrap();
+		let mut tetris = Tetris::with(ruleset.setup(0));
+
+		for _ in 0..100 {
+			tetris.spawn();
+			tetris.react_to(Msg::Clockwise);
+			tetris.hard_drop();
+			tetris.add_garbage(4, &[0]);
+			if tetris.is_over() {
+				break;
+			}
+		}
+		assert!(tetris.is_over());
+	}
+}
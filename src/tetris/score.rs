@@ -1,6 +1,6 @@
 use std::{
     io::{
-        self, Read, Write, Error,
+        Read, Write, Error,
     },
     fs::File,
 };
@@ -21,7 +21,7 @@ pub fn update_score(file: &mut File,
 
 pub fn upload_score(file: &mut File) -> Option<(Vec<u32>, Vec<u32>)> {
     let mut content = String::new();
-    if let Err(_) = file.read_to_string(&mut content) {
+    if file.read_to_string(&mut content).is_err() {
         return None;
     }
 
@@ -0,0 +1,108 @@
+use {
+	crate::game::Msg,
+	super::{
+		geometry::{
+			Coord,
+			Direction::*,
+		},
+		map::Map,
+		tetrimino::Tetrimino,
+	},
+	std::collections::{
+		HashMap,
+		HashSet,
+		VecDeque,
+	},
+};
+
+
+// final resting position of the tetrimino and how to get there
+#[derive(Debug, Clone)]
+pub struct Placement {
+	// sorted blocks of the tetrimino
+	pub blocks: Vec<Coord<i8>>,
+	// the shortest sequence of events, the tetrimino dies on the next descent
+	pub moves: Vec<Msg>,
+	pub(super) tetrimino: Tetrimino,
+}
+
+
+// all events the tetrimino can be moved by
+const MOVES: [Msg; 5] = [
+	Msg::ShiftLft,
+	Msg::ShiftRgt,
+	Msg::Clockwise,
+	Msg::Counterclockwise,
+	Msg::Proceed,
+];
+
+
+// breadth-first search over all positions reachable from the given one,
+// the map is copied so the game itself isn't changed
+pub fn placements(map: &Map, tetrimino: &Tetrimino) -> Vec<Placement> {
+	// tetrimino is drawn on it at any position that is being checked
+	let mut map = map.clone();
+
+	let mut queue = VecDeque::from([tetrimino.clone()]);
+	// how each position was reached, the start one has no parent
+	let mut parents: HashMap<Tetrimino, Option<(Tetrimino, Msg)>> =
+		HashMap::from([(tetrimino.clone(), None)]);
+
+	let mut placements = vec!();
+	let mut found: HashSet<Vec<Coord<i8>>> = HashSet::new();
+
+	while let Some(curr) = queue.pop_front() {
+		for msg in MOVES {
+			let mut next = curr.clone();
+
+			let moved = match msg {
+				Msg::ShiftLft => next.shift(Lft, &mut map),
+				Msg::ShiftRgt => next.shift(Rgt, &mut map),
+				Msg::Clockwise => next.rotate(true, &mut map),
+				Msg::Counterclockwise => next.rotate(false, &mut map),
+				_ => next.shift(Dwn, &mut map),
+			};
+
+			if moved && !parents.contains_key(&next) {
+				parents.insert(next.clone(), Some((curr.clone(), msg)));
+				queue.push_back(next);
+			}
+		}
+
+		// it dies here if it can't be descented
+		let mut below = curr.clone();
+		if below.shift(Dwn, &mut map) {
+			continue;
+		}
+
+		let mut blocks: Vec<Coord<i8>> = curr.iter().collect();
+		blocks.sort_by_key(|block| (block.x, block.y));
+
+		// different positions may occupy the same blocks
+		if found.insert(blocks.clone()) {
+			placements.push(Placement {
+				blocks,
+				moves: path(&parents, &curr),
+				tetrimino: curr,
+			});
+		}
+	}
+
+	placements
+}
+
+// events leading from the start position to the given one
+fn path(parents: &HashMap<Tetrimino, Option<(Tetrimino, Msg)>>,
+		tetrimino: &Tetrimino) -> Vec<Msg>
+{
+	let mut moves = vec!();
+	let mut curr = tetrimino;
+
+	while let Some(Some((parent, msg))) = parents.get(curr) {
+		moves.push(msg.clone());
+		curr = parent;
+	}
+
+	moves.reverse();
+	moves
+}
@@ -1,10 +1,8 @@
-#![allow(non_snake_case)]
-
 use {
 	crate::{
 		color::{
 			Color,
-			N_COLORS,
+			PIECE_COLORS,
 		},
 	},
 	super::{
@@ -14,46 +12,92 @@ use {
 				*,
 			},
 			Coord,
-			Segment,
 			Rectangle,
 		},
+		piece::Piece,
 		random::{
 			non_serial_rnd,
-			shuffle_colors,
+			shuffled_bag,
+			Randomizer,
 		},
 		map::Map,
 	},
+	rand::{
+		rngs::StdRng,
+		Rng,
+		SeedableRng,
+	},
+	std::{
+		collections::VecDeque,
+		hash::{
+			Hash,
+			Hasher,
+		},
+		slice::Iter,
+		sync::Arc,
+	},
 };
 
 
-pub const N_TETRIMINOS: u8 = 7;
+// number of the next tetriminos that are known in advance
+pub const PREVIEW: usize = 5;
 
 
-#[derive(Debug)]
+#[derive(Debug, Clone)]
 pub struct Tetrimino {
-	// it's |+| in following tetrimino schemes
+	// blocks of the piece are placed relatively to it
 	center: Coord<i8>,
-	// pos of remaining blocks
-	shape: [Direction; 3],
-	// rel means that each block's pos defines relatively to prev one
-	// abs (not rel) means exactly the same but relatively to center
-	is_rel: bool,
+	// index of the rotation state in piece states
+	state: usize,
+	piece: Arc<Piece>,
 	// only the same tetriminos may have the same color
 	pub color: Color,
+	// serial number, it's the same for the same tetriminos
+	pub id: u8,
+}
+
+// the piece is defined by the serial number, so it's not compared
+impl PartialEq for Tetrimino {
+	fn eq(&self, other: &Tetrimino) -> bool {
+		self.center == other.center &&
+		self.state == other.state &&
+		self.id == other.id
+	}
+}
+
+impl Eq for Tetrimino {}
+
+impl Hash for Tetrimino {
+	fn hash<H: Hasher>(&self, state: &mut H) {
+		self.center.hash(state);
+		self.state.hash(state);
+		self.id.hash(state);
+	}
 }
 
 
 impl Tetrimino {
 	// provides iterating by tetrimino's block coordinates
-	pub fn iter(&self) -> TetriminoIter {
+	pub fn iter(&self) -> TetriminoIter<'_> {
 		TetriminoIter {
-			shape: &self.shape,
-			is_rel: self.is_rel,
-			index: 0,
-			point: self.center,
+			blocks: self.piece.states[self.state].iter(),
+			center: self.center,
 		}
 	}
 
+	pub fn center(&self) -> Coord<i8> {
+		self.center
+	}
+
+	pub fn letter(&self) -> char {
+		self.piece.letter()
+	}
+
+	// only the tetromino T may be spun
+	pub fn is_t(&self) -> bool {
+		self.piece.name == "T" && self.piece.size() == 4
+	}
+
 	// returns bool means was shifting successful or not
 	pub fn shift(&mut self, dir: Direction, map: &mut Map) -> bool {
 		// shifting to the top is not allowed
@@ -76,15 +120,29 @@ impl Tetrimino {
 		}
 	}
 
+	// moves tetrimino up until it fits on the map,
+	// returns bool means was it moved or not
+	pub fn lift(&mut self, map: &mut Map) -> bool {
+		let mut lifted = false;
+		while !map.put(self) {
+			self.center += (0, 1);
+			lifted = true;
+		}
+		lifted
+	}
+
 	// returns bool means was rotation successful or not
 	pub fn rotate(&mut self, clockwise: bool, map: &mut Map) -> bool {
 		// bounds before rotation
 		let old_bounds = self.bounds();
 
-		// rotation itself, regardless to is_rel
-		for direction in &mut self.shape {
-			direction.rotate(clockwise);
-		}
+		// rotation itself
+		let states = self.piece.states.len();
+		let old_state = self.state;
+		self.state = match clockwise {
+			true => (self.state + 1) % states,
+			false => (self.state + states - 1) % states,
+		};
 
 		// bounds after rotation
 		let mut new_bounds = self.bounds();
@@ -139,10 +197,8 @@ impl Tetrimino {
 		}
 
 		// it's reachable only if rotation is impossible
-		for direction in &mut self.shape {
-			self.center -= &motion;	// not from for loop
-			direction.rotate(!clockwise);
-		}
+		self.center -= &motion;	// not from for loop
+		self.state = old_state;
 		
 		false
 	}
@@ -163,115 +219,122 @@ impl Tetrimino {
 }
 
 
-#[derive(Debug)]
+#[derive(Debug, Clone)]
 pub struct Generator {
-	// tetrimino color can be defined as color_picker elem
-	// with number which is equal to tetrimino's serial number
-	color_picker: [Color; N_COLORS],
+	// the same seed gives the same sequence of tetriminos
+	rng: StdRng,
+	// serial number of the previous tetrimino
+	prev: u8,
+	// serial numbers of the next tetriminos
+	next: VecDeque<u8>,
+	randomizer: Randomizer,
+	// serial numbers left in the current bag or the fixed queue, the next is the last
+	bag: Vec<u8>,
+	// serial number of the tetrimino is its index here
+	pieces: Vec<Arc<Piece>>,
 }
 
 
+// the rng can't be hashed itself, a number drawn from a copy of it stands for its state
+impl Hash for Generator {
+	fn hash<H: Hasher>(&self, state: &mut H) {
+		self.rng.clone().gen::<u64>().hash(state);
+		self.prev.hash(state);
+		self.next.hash(state);
+		self.bag.hash(state);
+	}
+}
+
 impl Generator {
-	pub fn new() -> Generator {
-		Generator {
-			color_picker: shuffle_colors(),
+	// the same seed gives the same tetriminos
+	pub fn new(seed: u64, randomizer: Randomizer, pieces: Vec<Piece>) -> Generator {
+		let rng = StdRng::seed_from_u64(seed);
+		let bag = match &randomizer {
+			Randomizer::Fixed(queue) => queue.iter().rev().copied().collect(),
+			_ => vec!(),
+		};
+
+		let mut generator = Generator {
+			rng,
+			prev: 0,
+			next: VecDeque::new(),
+			randomizer,
+			bag,
+			pieces: pieces.into_iter().map(Arc::new).collect(),
+		};
+
+		for _ in 0..PREVIEW {
+			if let Some(id) = generator.next_id() {
+				generator.next.push_back(id);
+			}
 		}
-	}
 
-	// last color in shuffled is for map bg
-	pub fn map_bg(&self) -> Color {
-		self.color_picker[N_COLORS - 1]
+		generator
 	}
 
-	// random tetrimino differs from previous one
-	pub fn gen(&self) -> Tetrimino {
-		let id = non_serial_rnd(N_TETRIMINOS);
-
-		match id {
-			0 => self.I(),
-			1 => self.J(),
-			2 => self.L(),
-			3 => self.O(),
-			4 => self.S(),
-			5 => self.Z(),
-			6 => self.T(),
-			_ => unreachable!(),  // panics if compiler forgets how to count
+	// serial number of the tetrimino that will come after the preview,
+	// only the fixed queue may run out
+	fn next_id(&mut self) -> Option<u8> {
+		let kinds = self.kinds() as u8;
+
+		match self.randomizer {
+			Randomizer::Classic =>
+				Some(non_serial_rnd(&mut self.rng, &mut self.prev, kinds)),
+			Randomizer::Bag => {
+				if self.bag.is_empty() {
+					self.bag = shuffled_bag(&mut self.rng, kinds);
+				}
+				self.prev = self.bag.pop()?;
+				Some(self.prev)
+			}
+			Randomizer::Fixed(_) => {
+				self.prev = self.bag.pop()?;
+				Some(self.prev)
+			}
 		}
 	}
 
-	fn I(&self) -> Tetrimino {
-		Tetrimino {									//		_
-			center: Map::top() + (0, 3),			//	   |+|
-			shape: [Dwn, Dwn, Dwn],					//	   |‾|
-			is_rel: true,							//	   |‾|
-			color: self.color_picker[0],  			//	   |‾|
-		}											//		‾
-	}
-
-	fn J(&self) -> Tetrimino {
-		Tetrimino {
-			center: Map::top() + (0, 2),			//		  _
-			shape: [Dwn, Dwn, Lft],					//		 |+|
-			is_rel: true,				 			//		 |‾|
-			color: self.color_picker[1],  			//	   |‾|‾|
-		}											//	    ‾ ‾
+	// number of different tetriminos
+	pub fn kinds(&self) -> usize {
+		self.pieces.len()
 	}
 
-	fn L(&self) -> Tetrimino {
-		Tetrimino {
-			center: Map::top() + (0, 2),			//		_
-			shape: [Dwn, Dwn, Rgt],					//	   |+|
-			is_rel: true,							//	   |‾|
-			color: self.color_picker[2],  			//	   |‾|‾|
-		}											//		‾ ‾
+	// serial numbers of the next tetriminos in the order they will come
+	pub fn preview(&self) -> impl Iterator<Item = u8> + '_ {
+		self.next.iter().copied()
 	}
 
-	fn O(&self) -> Tetrimino {
-		Tetrimino {
-			center: Map::top() + (0, 1),			//		_ _
-			shape: [Rgt, Dwn, Lft],		 			//	   |+| |
-			is_rel: true,							//	   |‾|‾|
-			color: self.color_picker[3],  			//		‾ ‾
+	// the next random tetrimino spawning at top, if there are any left
+	pub fn gen(&mut self, top: Coord<i8>) -> Option<Tetrimino> {
+		if let Some(id) = self.next_id() {
+			self.next.push_back(id);
 		}
-	}
 
-	fn S(&self) -> Tetrimino {
-		Tetrimino {
-			center: Map::top(),						//		  _ _
-			shape: [Rgt, Top, Rgt],					//		_|_|_|
-			is_rel: true,							//	   |+| |
-			color: self.color_picker[4],  			//		‾ ‾
-		}
+		let id = self.next.pop_front()?;
+		Some(self.make(id, top))
 	}
 
-	fn Z(&self) -> Tetrimino {
-		Tetrimino {
-			center: Map::top() + (0, 1),			//		_ _
-			shape: [Rgt, Dwn, Rgt],					//	   |+| |
-			is_rel: true,							//		‾|‾|‾|
-			color: self.color_picker[5],  			//		  ‾ ‾
-		}
-	}
+	// tetrimino with the given serial number spawning at top
+	pub fn make(&self, id: u8, top: Coord<i8>) -> Tetrimino {
+		let piece = &self.pieces[id as usize];
 
-	fn T(&self) -> Tetrimino {
 		Tetrimino {
-			center: Map::top(),						//		  _
-			shape: [Lft, Top, Rgt],					//		_|_|_
-			is_rel: false,							//	   | |+| |
-			color: self.color_picker[6],  			//		‾ ‾ ‾
+			center: top + (piece.spawn.x, piece.spawn.y),
+			state: 0,
+			piece: piece.clone(),
+			// there are more pieces than colors in some sets, so piece id and
+			// id + 7 look the same unless the theme colors them by letters
+			color: piece.color.unwrap_or(PIECE_COLORS[id as usize % PIECE_COLORS.len()]),
+			id,
 		}
 	}
 }
 
 
 pub struct TetriminoIter<'a> {
-	// exactly the same shape and is_rel as in tetrimino created iter
-	shape: &'a [Direction; 3],
-	is_rel: bool,
-	// number of block whose point will be yielded by next()
-	index: usize,
-	// point that is needed to compute next
-	point: Coord<i8>,
+	// blocks of the current rotation state
+	blocks: Iter<'a, Coord<i8>>,
+	center: Coord<i8>,
 }
 
 
@@ -279,37 +342,34 @@ impl Iterator for TetriminoIter<'_> {
 	type Item = Coord<i8>;
 
 	fn next(&mut self) -> Option<Self::Item> {
-		// there are only four blocks
-		if self.index >= 4 {
-			return None
-		}
-
-		// returning center
-		if self.index == 0 {
-			self.index += 1;
-			return Some(self.point);
-		}
-
-		// computing item relatively to point
-		let mut item = self.point;
+		let block = self.blocks.next()?;
+		Some(self.center + (block.x, block.y))
+	}
+}
 
-		item += &self.shape[self.index - 1];
 
-		// match self.shape[self.index - 1] {
-		// 	Top => item += (0, 1),
-		// 	Rgt => item += (1, 0),
-		// 	Dwn => item += (0, -1),
-		// 	Lft => item += (-1, 0),
-		// 	_ => (),
-		// }
+#[cfg(test)]
+mod tests {
+	use {
+		super::*,
+		crate::tetris::tetrominoes,
+		std::collections::hash_map::DefaultHasher,
+	};
 
-		self.index += 1;
+	fn digest(generator: &Generator) -> u64 {
+		let mut hasher = DefaultHasher::new();
+		generator.hash(&mut hasher);
+		hasher.finish()
+	}
 
-		// is_rel means next will be computing relatively to prev
-		if self.is_rel {
-			self.point = item;
-		}
+	// the divergence is seen before it changes a tetrimino
+	#[test]
+	fn hash_includes_rng() {
+		let generator = Generator::new(0, Randomizer::Classic, tetrominoes());
+		let mut other = generator.clone();
+		assert_eq!(digest(&generator), digest(&other));
 
-		Some(item)
+		other.rng.gen::<u64>();
+		assert_ne!(digest(&generator), digest(&other));
 	}
 }
@@ -0,0 +1,200 @@
+// colors blocks are drawn with, besides the built in themes
+// there may be files of any in the config dir like themes/mine.toml
+//
+//	background = "#000000"
+//	garbage = "#808080"
+//
+//	[pieces]					# by letters pieces are written with in boards
+//	I = "#00f0f0"
+//	n = "#ff8800"				# mirrored N'
+//
+//	[palette]					# the rest by colors they are given, like in rulesets
+//	pink = "#ffccbb"
+//
+// what isn't set is taken from the classic theme
+
+use {
+	iced::Color as IcedColor,
+	crate::{
+		color::Color,
+		tetris::Tile,
+	},
+	serde::{
+		Deserialize,
+		Serialize,
+	},
+	std::{
+		collections::BTreeMap,
+		fmt,
+		fs,
+		path::{
+			Path,
+			PathBuf,
+		},
+		str::FromStr,
+	},
+};
+
+
+// names of the built in themes, the first one is used if no theme is set
+pub const BUILT_IN: [&str; 5] = ["classic", "guideline", "nes", "dark", "high-contrast"];
+
+
+// a color written like #f0a000
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(try_from = "String", into = "String")]
+pub struct Rgb(pub u8, pub u8, pub u8);
+
+impl Rgb {
+	pub fn to_iced(self) -> IcedColor {
+		IcedColor::from_rgb8(self.0, self.1, self.2)
+	}
+}
+
+impl fmt::Display for Rgb {
+	fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+		write!(f, "#{:02x}{:02x}{:02x}", self.0, self.1, self.2)
+	}
+}
+
+impl FromStr for Rgb {
+	type Err = String;
+
+	fn from_str(string: &str) -> Result<Rgb, String> {
+		let invalid = || format!("invalid color {string}, it's written like #f0a000");
+		let hex = string.strip_prefix('#').filter(|hex| hex.len() == 6).ok_or_else(invalid)?;
+		let channel = |i: usize| {
+			hex.get(i..i + 2)
+				.and_then(|channel| u8::from_str_radix(channel, 16).ok())
+				.ok_or_else(invalid)
+		};
+
+		Ok(Rgb(channel(0)?, channel(2)?, channel(4)?))
+	}
+}
+
+impl TryFrom<String> for Rgb {
+	type Error = String;
+
+	fn try_from(string: String) -> Result<Rgb, String> {
+		string.parse()
+	}
+}
+
+impl From<Rgb> for String {
+	fn from(rgb: Rgb) -> String {
+		rgb.to_string()
+	}
+}
+
+
+// the palette of the classic theme, colors the rest of themes don't set
+fn classic(color: Color) -> Rgb {
+	match color {
+		Color::Red => Rgb(159,  70,  54),
+		Color::Grn => Rgb(  7,  87,  91),
+		Color::Blu => Rgb(120, 165, 163),
+		Color::Wht => Rgb(241, 220, 201),
+		Color::Ylw => Rgb(225, 177, 106),
+		Color::Pnk => Rgb(255, 204, 187),
+		Color::Vin => Rgb( 30,   0,   0),
+		Color::Gry => Rgb(254, 155, 151),
+		Color::Grb => Rgb(128, 128, 128),
+		Color::Non => Rgb(  0,   0,   0),
+	}
+}
+
+
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+#[serde(default)]
+pub struct Theme {
+	// color of empty blocks
+	pub background: Rgb,
+	pub garbage: Rgb,
+	// colors of pieces by their letters
+	pub pieces: BTreeMap<String, Rgb>,
+	// colors of the rest of blocks by their colors
+	pub palette: BTreeMap<String, Rgb>,
+}
+
+impl Default for Theme {
+	fn default() -> Theme {
+		Theme {
+			background: classic(Color::Vin),
+			garbage: classic(Color::Grb),
+			pieces: BTreeMap::new(),
+			palette: Color::all()
+				.into_iter()
+				.map(|color| (String::from(color.name()), classic(color)))
+				.collect(),
+		}
+	}
+}
+
+impl Theme {
+	// the built in theme by its name, tetrominoes get their own colors
+	pub fn named(name: &str) -> Option<Theme> {
+		let rgb = |hex: &str| hex.parse::<Rgb>().unwrap();
+		// background, garbage and pieces in the order of I, J, L, O, S, Z, T
+		let (background, garbage, pieces) = match name {
+			"" | "classic" => return Some(Theme::default()),
+			"guideline" => ("#000000", "#808080",
+				["#00f0f0", "#0000f0", "#f0a000", "#f0f000", "#00f000", "#f00000", "#a000f0"]),
+			"nes" => ("#000000", "#747474",
+				["#fcfcfc", "#0058f8", "#3cbcfc", "#fcfcfc", "#0058f8", "#3cbcfc", "#fcfcfc"]),
+			"dark" => ("#121212", "#3a3a3a",
+				["#4fa3a5", "#5470b0", "#c0874a", "#c9b458", "#6aa56a", "#b85c5c", "#8e6cb8"]),
+			"high-contrast" => ("#000000", "#c0c0c0",
+				["#00ffff", "#6699ff", "#ff8800", "#ffff00", "#00ff00", "#ff0000", "#ff00ff"]),
+			_ => return None,
+		};
+
+		Some(Theme {
+			background: rgb(background),
+			garbage: rgb(garbage),
+			pieces: ["I", "J", "L", "O", "S", "Z", "T"]
+				.into_iter()
+				.map(String::from)
+				.zip(pieces.map(rgb))
+				.collect(),
+			..Theme::default()
+		})
+	}
+
+	pub fn dir() -> Option<PathBuf> {
+		dirs::config_dir().map(|dir| dir.join("tetris").join("themes"))
+	}
+
+	// a built in theme, a theme in the config dir by its name or a file by its path
+	pub fn load(name: &str) -> Result<Theme, String> {
+		if let Some(theme) = Theme::named(name) {
+			return Ok(theme);
+		}
+
+		let path = match Path::new(name).extension() {
+			Some(_) => PathBuf::from(name),
+			None => Theme::dir()
+				.ok_or_else(|| String::from("no config dir"))?
+				.join(format!("{name}.toml")),
+		};
+		let content = fs::read_to_string(&path)
+			.map_err(|err| format!("{}: {err}", path.display()))?;
+		toml::from_str(&content)
+			.map_err(|err| format!("{}: {err}", path.display()))
+	}
+
+	// pieces the theme has no color for are drawn by their colors
+	pub fn color(&self, tile: Tile) -> IcedColor {
+		let mut letter = [0; 4];
+		let rgb = match tile.color {
+			Color::Non => self.background,
+			Color::Grb => self.garbage,
+			color => self.pieces
+				.get(&*tile.kind.encode_utf8(&mut letter))
+				.or_else(|| self.palette.get(color.name()))
+				.copied()
+				.unwrap_or_else(|| classic(color)),
+		};
+		rgb.to_iced()
+	}
+}
@@ -0,0 +1,123 @@
+use {
+	crate::{
+		game::Msg,
+		tetris::Tetris,
+	},
+	rand::{
+		random,
+		rngs::StdRng,
+		Rng,
+		SeedableRng,
+	},
+	std::time::Duration,
+};
+
+
+// rows of garbage sent to the opponent for burning 0, 1, 2, 3 or 4 lines
+pub const ATTACK: [usize; 5] = [0, 0, 1, 2, 4];
+
+
+// two games side by side, burned lines go to the opponent as garbage
+#[derive(Clone)]
+pub struct Versus {
+	boards: [Tetris; 2],
+	// picks holes in garbage
+	rng: StdRng,
+}
+
+impl Default for Versus {
+	fn default() -> Versus {
+		Versus::new()
+	}
+}
+
+impl Versus {
+	pub fn new() -> Versus {
+		Versus::seeded(random())
+	}
+
+	// both players get the same tetriminos
+	pub fn seeded(seed: u64) -> Versus {
+		Versus {
+			boards: [Tetris::seeded(seed), Tetris::seeded(seed)],
+			rng: StdRng::seed_from_u64(seed),
+		}
+	}
+
+	// advances the clock of both games
+	pub fn tick(&mut self, dt: Duration) {
+		if self.is_over() {
+			return;
+		}
+
+		for player in 0..2 {
+			let lines = self.boards[player].lines();
+			self.boards[player].tick(dt);
+			self.attack(player, lines);
+		}
+	}
+
+	// handling the event of the given player
+	pub fn react_to(&mut self, player: usize, msg: Msg) {
+		if self.is_over() {
+			return;
+		}
+
+		let lines = self.boards[player].lines();
+		self.boards[player].react_to(msg);
+		self.attack(player, lines);
+	}
+
+	// sends garbage for the lines burned since the player had given lines
+	fn attack(&mut self, player: usize, lines: usize) {
+		let burned = self.boards[player].lines() - lines;
+		let rows = ATTACK[burned.min(4)];
+
+		if rows > 0 {
+			let hole = self.rng.gen_range(0..10);
+			self.boards[1 - player].add_garbage(rows, &[hole]);
+		}
+	}
+
+	// the match ends when one of the boards tops out
+	pub fn is_over(&self) -> bool {
+		self.boards.iter().any(|board| board.is_over())
+	}
+
+	// the player who didn't top out
+	pub fn winner(&self) -> Option<usize> {
+		match (self.boards[0].is_over(), self.boards[1].is_over()) {
+			(true, false) => Some(1),
+			(false, true) => Some(0),
+			_ => None,
+		}
+	}
+
+	// the rng is told by a number drawn from a copy of it
+	pub fn checksum(&self) -> u64 {
+		self.boards[0].checksum() ^
+		self.boards[1].checksum().rotate_left(1) ^
+		self.rng.clone().gen::<u64>().rotate_left(2)
+	}
+
+	pub fn boards(&self) -> &[Tetris; 2] {
+		&self.boards
+	}
+}
+
+
+#[cfg(test)]
+mod tests {
+	use super::*;
+
+	// garbage holes would differ later on
+	#[test]
+	fn checksum_includes_rng() {
+		let versus = Versus::seeded(0);
+		let mut other = versus.clone();
+		assert_eq!(versus.checksum(), other.checksum());
+
+		other.rng.gen::<u64>();
+		assert_ne!(versus.checksum(), other.checksum());
+	}
+}